@@ -0,0 +1,72 @@
+//! Throughput of every format: `cargo bench`, or `cargo bench -- api` for
+//! one. `generator` is the `--stream` hot path (no allocation per value);
+//! `generate` is the one-off `generate_with_length` call for comparison;
+//! `encodings` runs `bytes` through each `-e` encoding on the hot path.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use randr::{ByteEncoding, Constraint, Generator, RandomFormat, Style, TagKind};
+
+fn style() -> Style {
+    // A fixed tag keeps word formats comparable from run to run.
+    Style::random().with_tag(TagKind::Digits(4))
+}
+
+fn generator(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generator");
+    group.throughput(Throughput::Elements(1));
+    for format in RandomFormat::all() {
+        let length = format.default_length().unwrap_or(0);
+        let rng = StdRng::seed_from_u64(0);
+        let mut generator = Generator::with_rng(format, style(), length, rng);
+        group.bench_function(format.short_name(), |b| {
+            b.iter(|| black_box(generator.next_str().len()))
+        });
+    }
+    group.finish();
+}
+
+fn generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    group.throughput(Throughput::Elements(1));
+    let style = style();
+    for format in RandomFormat::all() {
+        let length = format.default_length().unwrap_or(0);
+        group.bench_function(format.short_name(), |b| {
+            b.iter(|| randr::generate_with_length(black_box(format), &style, length))
+        });
+    }
+    group.finish();
+}
+
+fn encodings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encodings");
+    group.throughput(Throughput::Elements(1));
+    for encoding in ByteEncoding::all() {
+        let rng = StdRng::seed_from_u64(0);
+        let length = randr::BYTES_LENGTH;
+        let mut generator = Generator::with_rng(RandomFormat::Bytes, style(), length, rng)
+            .with_encoding(encoding);
+        group.bench_function(encoding.short_name(), |b| {
+            b.iter(|| black_box(generator.next_str().len()))
+        });
+    }
+    group.finish();
+}
+
+fn constrained(c: &mut Criterion) {
+    let mut group = c.benchmark_group("constrained");
+    group.throughput(Throughput::Elements(1));
+    for constraint in [Constraint::Dns, Constraint::RustIdent] {
+        let style = Style::for_target(constraint);
+        let mut generator = Generator::new(RandomFormat::MemorableName, style, 0);
+        group.bench_function(constraint.short_name(), |b| {
+            b.iter(|| black_box(generator.next_str().len()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generator, generate, encodings, constrained);
+criterion_main!(benches);
@@ -0,0 +1,179 @@
+//! `--stream` output, on one thread or `--jobs N`. Values are made in
+//! fixed-size chunks and written in chunk order, so the output doesn't
+//! depend on the number of threads: with `--seed` it is the same for any
+//! `--jobs`.
+//!
+//! Chunks are handed out in order from one `Plan`: each gets its own RNG
+//! keyed from the master RNG, and `uuidv7` chunks a reserved run of
+//! timestamp + counter, so v7 IDs stay sorted across threads.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use randr::{ByteEncoding, Generator, RandomFormat, Style, V7Block, V7Clock};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+
+/// Values per chunk: big enough that threads rarely touch the plan, small
+/// enough that `-n 100` doesn't wait on a huge first chunk.
+pub const CHUNK: usize = 16 * 1024;
+
+pub struct Job<'a> {
+    pub format: RandomFormat,
+    pub style: Style,
+    pub length: usize,
+    pub encoding: ByteEncoding,
+    /// Stop after this many values; `None` streams until the write fails.
+    pub count: Option<usize>,
+    pub jobs: usize,
+    /// Writes one value, newline included, into a chunk's buffer.
+    pub line: &'a (dyn Fn(&str, &mut Vec<u8>) + Sync),
+}
+
+struct Chunk {
+    index: usize,
+    len: usize,
+    rng: StdRng,
+    v7: Option<V7Block>,
+}
+
+/// What's left to hand out.
+struct Plan {
+    master: StdRng,
+    v7: Option<V7Clock>,
+    next: usize,
+    left: Option<usize>,
+}
+
+impl Plan {
+    fn take(&mut self) -> Option<Chunk> {
+        let len = self.left.map_or(CHUNK, |left| left.min(CHUNK));
+        if len == 0 {
+            return None;
+        }
+        if let Some(left) = &mut self.left {
+            *left -= len;
+        }
+        let chunk = Chunk {
+            index: self.next,
+            len,
+            rng: StdRng::from_seed(self.master.gen()),
+            v7: self.v7.as_mut().map(|clock| clock.reserve(len as u64)),
+        };
+        self.next += 1;
+        Some(chunk)
+    }
+}
+
+fn fill(job: &Job, chunk: Chunk) -> Vec<u8> {
+    let mut generator = Generator::with_rng(job.format, job.style.clone(), job.length, chunk.rng)
+        .with_encoding(job.encoding);
+    if let Some(block) = chunk.v7 {
+        generator.use_v7_block(block);
+    }
+    let mut buf = Vec::with_capacity(chunk.len * 40);
+    for _ in 0..chunk.len {
+        (job.line)(generator.next_str(), &mut buf);
+    }
+    buf
+}
+
+/// Run `job` into `out`, keying every chunk from `master`.
+pub fn run(job: &Job, master: StdRng, out: &mut impl Write) -> io::Result<()> {
+    let plan = Plan {
+        master,
+        v7: (job.format == RandomFormat::UuidV7).then(V7Clock::new),
+        next: 0,
+        left: job.count,
+    };
+    if job.jobs <= 1 {
+        let mut plan = plan;
+        while let Some(chunk) = plan.take() {
+            out.write_all(&fill(job, chunk))?;
+        }
+        return out.flush();
+    }
+
+    let plan = Mutex::new(plan);
+    let stop = AtomicBool::new(false);
+    // Bounded, so workers can't race far ahead of a slow writer.
+    let (tx, rx) = mpsc::sync_channel::<(usize, Vec<u8>)>(job.jobs * 2);
+    std::thread::scope(|scope| {
+        for _ in 0..job.jobs {
+            let tx = tx.clone();
+            let (plan, stop) = (&plan, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let Some(chunk) = plan.lock().unwrap().take() else {
+                        break;
+                    };
+                    let index = chunk.index;
+                    if tx.send((index, fill(job, chunk))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // Chunks finish out of order; hold each until its turn.
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        let result = rx.iter().try_for_each(|(index, buf)| {
+            pending.insert(index, buf);
+            while let Some(buf) = pending.remove(&next) {
+                out.write_all(&buf)?;
+                next += 1;
+            }
+            Ok(())
+        });
+        // On a failed write, stop handing out chunks and let the workers
+        // see the closed channel.
+        stop.store(true, Ordering::Relaxed);
+        drop(rx);
+        result.and_then(|()| out.flush())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(format: RandomFormat, count: usize, jobs: usize, seed: u64) -> Vec<String> {
+        let line = |value: &str, buf: &mut Vec<u8>| {
+            buf.extend_from_slice(value.as_bytes());
+            buf.push(b'\n');
+        };
+        let mut master = StdRng::seed_from_u64(seed);
+        let job = Job {
+            format,
+            style: Style::random_with(&mut master),
+            length: format.default_length().unwrap_or(0),
+            encoding: ByteEncoding::default(),
+            count: Some(count),
+            jobs,
+            line: &line,
+        };
+        let mut out = Vec::new();
+        run(&job, master, &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_same_output_for_any_jobs() {
+        let count = CHUNK * 3 + 5;
+        let one = lines(RandomFormat::MemorableName, count, 1, 42);
+        assert_eq!(one.len(), count);
+        assert_eq!(lines(RandomFormat::MemorableName, count, 4, 42), one);
+        assert_ne!(lines(RandomFormat::MemorableName, count, 4, 43), one);
+        assert_eq!(lines(RandomFormat::ApiKey, 10, 3, 7), lines(RandomFormat::ApiKey, 10, 1, 7));
+    }
+
+    #[test]
+    fn test_v7_stays_sorted() {
+        let ids = lines(RandomFormat::UuidV7, CHUNK * 4, 4, 1);
+        assert_eq!(ids.len(), CHUNK * 4);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+}
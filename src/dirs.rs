@@ -0,0 +1,47 @@
+//! Per-user directories from the XDG Base Directory spec, for the ledger
+//! (data) and the last-grid cache.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// `$XDG_DATA_HOME`, else `~/.local/share`.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// `$XDG_CACHE_HOME`, else `~/.cache`.
+pub fn cache_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    resolve(std::env::var_os(var), std::env::var_os("HOME"), fallback)
+}
+
+/// The spec says an empty or relative `XDG_*` value is to be ignored, as
+/// if unset; `None` when there's no usable `$HOME` to fall back on either.
+fn resolve(value: Option<OsString>, home: Option<OsString>, fallback: &str) -> Option<PathBuf> {
+    let home = home.filter(|home| !home.is_empty());
+    value
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home.map(|home| PathBuf::from(home).join(fallback)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let home = || Some(OsString::from("/home/me"));
+        let set = |value: &str| Some(OsString::from(value));
+        assert_eq!(resolve(set("/xdg"), home(), ".cache"), Some(PathBuf::from("/xdg")));
+        for ignored in [None, set(""), set("relative/dir")] {
+            let dir = resolve(ignored, home(), ".cache");
+            assert_eq!(dir, Some(PathBuf::from("/home/me/.cache")));
+        }
+        assert_eq!(resolve(None, None, ".cache"), None);
+        assert_eq!(resolve(set(""), set(""), ".cache"), None);
+    }
+}
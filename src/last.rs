@@ -0,0 +1,95 @@
+//! Labels for grid items (`a1`, `b3`: section letter, position within the
+//! section) and the cache of the last printed grid, so `randr last b3` can
+//! re-emit an item after the fact.
+//!
+//! The cache is a tab-separated `label value` file that each grid run
+//! overwrites. It's a convenience: failing to write it never fails a run.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Label for item `item` of section `section` (both from 0): `a1`, `a2`,
+/// ..., `b1`, ... Sections past `z` continue `aa`, `ab`, ...
+pub fn label(section: usize, item: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = section;
+    loop {
+        letters.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    letters.reverse();
+    format!("{}{}", String::from_utf8(letters).unwrap(), item + 1)
+}
+
+/// Every item with its label, sections in order.
+pub fn labelled(sections: &[Vec<String>]) -> Vec<(String, String)> {
+    sections
+        .iter()
+        .enumerate()
+        .flat_map(|(s, items)| {
+            items
+                .iter()
+                .enumerate()
+                .map(move |(i, item)| (label(s, i), item.clone()))
+        })
+        .collect()
+}
+
+/// `$XDG_CACHE_HOME/randr/last.tsv`, falling back to
+/// `~/.cache/randr/last.tsv`.
+pub fn default_path() -> Option<PathBuf> {
+    Some(randr::dirs::cache_dir()?.join("randr").join("last.tsv"))
+}
+
+pub fn save(path: &std::path::Path, items: &[(String, String)]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut buf = String::new();
+    for (label, value) in items {
+        buf.push_str(&format!("{}\t{}\n", label, value));
+    }
+    fs::write(path, buf)
+}
+
+pub fn load(path: &std::path::Path) -> io::Result<Vec<(String, String)>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(label, value)| (label.to_string(), value.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label(0, 0), "a1");
+        assert_eq!(label(1, 2), "b3");
+        assert_eq!(label(25, 9), "z10");
+        assert_eq!(label(26, 0), "aa1");
+        assert_eq!(label(27, 0), "ab1");
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("randr-last-{}", std::process::id()))
+            .join("last.tsv");
+        let sections = vec![
+            vec!["brave-fox".to_string(), "calm-owl".to_string()],
+            vec!["lusab-babad".to_string()],
+        ];
+        let items = labelled(&sections);
+        save(&path, &items).unwrap();
+        assert_eq!(load(&path).unwrap(), items);
+        assert_eq!(items[2], ("b1".to_string(), "lusab-babad".to_string()));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,115 @@
+//! Grid layout with a width per column, like `ls`: a column is only as wide
+//! as its widest cell, so one long item doesn't widen the whole grid.
+
+/// Order cells are placed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Fill {
+    /// Left to right, then down.
+    Rows,
+    /// Top to bottom, then right.
+    Columns,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    pub fill: Fill,
+    pub rows: usize,
+    /// Width of each column, excluding the gap.
+    pub widths: Vec<usize>,
+    len: usize,
+}
+
+impl Grid {
+    /// The most columns that fit cells of `widths` in `avail` columns with
+    /// `gap` between them. Always at least one column, even if it overflows.
+    pub fn fit(widths: &[usize], avail: usize, gap: usize, fill: Fill) -> Grid {
+        let narrowest = widths.iter().copied().min().unwrap_or(0);
+        let most = std::cmp::min(widths.len(), (avail + gap) / (narrowest + gap).max(1));
+        (2..=most)
+            .rev()
+            .map(|cols| Grid::with_cols(widths, cols, fill))
+            .find(|grid| grid.total_width(gap) <= avail)
+            .unwrap_or_else(|| Grid::with_cols(widths, 1, fill))
+    }
+
+    fn with_cols(widths: &[usize], cols: usize, fill: Fill) -> Grid {
+        let len = widths.len();
+        let rows = len.div_ceil(cols).max(1);
+        // Column-major with fewer items than rows × cols can leave whole
+        // columns empty; only count the ones in use.
+        let cols = match fill {
+            Fill::Rows => cols.min(len),
+            Fill::Columns => len.div_ceil(rows),
+        };
+        let mut grid = Grid {
+            fill,
+            rows,
+            widths: vec![0; cols],
+            len,
+        };
+        for (i, w) in widths.iter().enumerate() {
+            let (_, col) = grid.position(i);
+            grid.widths[col] = grid.widths[col].max(*w);
+        }
+        grid
+    }
+
+    /// Columns the whole grid takes, gaps included.
+    pub fn total_width(&self, gap: usize) -> usize {
+        self.widths.iter().sum::<usize>() + gap * self.widths.len().saturating_sub(1)
+    }
+
+    pub fn cols(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// (row, column) of cell `i`.
+    pub fn position(&self, i: usize) -> (usize, usize) {
+        match self.fill {
+            Fill::Rows => (i / self.cols(), i % self.cols()),
+            Fill::Columns => (i % self.rows, i / self.rows),
+        }
+    }
+
+    /// Cell indices of each row, in column order.
+    pub fn rows(&self) -> Vec<Vec<usize>> {
+        let mut rows = vec![Vec::new(); self.rows];
+        for i in 0..self.len {
+            rows[self.position(i).0].push(i);
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_per_column_widths() {
+        // One uniform width would be 11 and allow only 2 columns in 30.
+        let widths = [11, 3, 3, 3, 3, 3];
+        let grid = Grid::fit(&widths, 30, 2, Fill::Rows);
+        assert_eq!(grid.widths, [11, 3, 3, 3]);
+        assert_eq!(grid.rows(), [vec![0, 1, 2, 3], vec![4, 5]]);
+
+        // Down the columns, the 11 shares column 0 with a 3, so the same
+        // width fits fewer columns: 5 or 4 would still need 2 rows.
+        let grid = Grid::fit(&widths, 30, 2, Fill::Columns);
+        assert_eq!(grid.widths, [11, 3, 3]);
+        assert_eq!(grid.rows(), [vec![0, 2, 4], vec![1, 3, 5]]);
+        let grid = Grid::fit(&widths, 40, 2, Fill::Columns);
+        assert_eq!(grid.rows, 1);
+        assert_eq!(grid.widths, widths);
+    }
+
+    #[test]
+    fn test_fit_edges() {
+        assert_eq!(Grid::fit(&[], 80, 2, Fill::Rows).rows(), [Vec::<usize>::new()]);
+        // Too wide for the terminal: one column, overflowing.
+        assert_eq!(Grid::fit(&[100, 100], 80, 2, Fill::Rows).widths, [100]);
+        // Column-major never leaves an empty column: 5 items in 2 rows.
+        let grid = Grid::fit(&[1; 5], 7, 2, Fill::Columns);
+        assert_eq!(grid.rows(), [vec![0, 2, 4], vec![1, 3]]);
+    }
+}
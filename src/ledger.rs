@@ -0,0 +1,233 @@
+//! Persistent record of issued values, so names handed out for
+//! environments, hosts and the like are never handed out twice.
+//!
+//! The ledger is a plain tab-separated file, one `namespace value issued_at`
+//! line per value. Every `Ledger` holds an exclusive lock on the file for
+//! its lifetime, so concurrent `randr` runs queue up instead of issuing the
+//! same value twice.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// One issued value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub namespace: String,
+    pub value: String,
+    /// Unix seconds.
+    pub issued_at: u64,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Entry> {
+        let mut fields = line.splitn(3, '\t');
+        let namespace = fields.next()?.to_string();
+        let value = fields.next()?.to_string();
+        let issued_at = fields.next()?.parse().ok()?;
+        Some(Entry {
+            namespace,
+            value,
+            issued_at,
+        })
+    }
+
+    /// The ledger line for this entry, without the newline.
+    pub fn to_line(&self) -> String {
+        format!("{}\t{}\t{}", self.namespace, self.value, self.issued_at)
+    }
+}
+
+/// `$XDG_DATA_HOME/randr/ledger.tsv`, falling back to
+/// `~/.local/share/randr/ledger.tsv`.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::dirs::data_dir()?.join("randr").join("ledger.tsv"))
+}
+
+/// An open, locked ledger file. The lock is released on drop.
+pub struct Ledger {
+    file: File,
+}
+
+impl Ledger {
+    /// Open (creating if needed) the ledger at `path` and take an exclusive
+    /// lock on it, blocking until other holders are done.
+    pub fn open(path: &Path) -> io::Result<Ledger> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.lock()?;
+        Ok(Ledger { file })
+    }
+
+    /// Every entry, in issue order. Lines that don't parse are skipped.
+    pub fn entries(&mut self) -> io::Result<Vec<Entry>> {
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(&self.file).lines() {
+            if let Some(entry) = Entry::parse(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Values already issued in `namespace`.
+    pub fn issued(&mut self, namespace: &str) -> io::Result<HashSet<String>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|e| e.namespace == namespace)
+            .map(|e| e.value)
+            .collect())
+    }
+
+    /// Issued-value counts per namespace.
+    pub fn namespaces(&mut self) -> io::Result<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        for entry in self.entries()? {
+            *counts.entry(entry.namespace).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Append `values` to `namespace` and flush them to disk.
+    pub fn record(&mut self, namespace: &str, values: &[String]) -> io::Result<()> {
+        check_field("namespace", namespace)?;
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut buf = String::new();
+        for value in values {
+            check_field("value", value)?;
+            let entry = Entry {
+                namespace: namespace.to_string(),
+                value: value.clone(),
+                issued_at,
+            };
+            buf.push_str(&entry.to_line());
+            buf.push('\n');
+        }
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(buf.as_bytes())?;
+        self.file.sync_data()
+    }
+
+    /// Drop `values` from `namespace` — or the whole namespace when
+    /// `values` is `None` — so they can be issued again. Returns how many
+    /// entries were removed.
+    pub fn forget(&mut self, namespace: &str, values: Option<&[String]>) -> io::Result<usize> {
+        let entries = self.entries()?;
+        let before = entries.len();
+        let kept: Vec<Entry> = entries
+            .into_iter()
+            .filter(|e| {
+                e.namespace != namespace || values.is_some_and(|v| !v.contains(&e.value))
+            })
+            .collect();
+        let removed = before - kept.len();
+        if removed > 0 {
+            let mut buf = String::new();
+            for entry in &kept {
+                buf.push_str(&entry.to_line());
+                buf.push('\n');
+            }
+            self.file.set_len(0)?;
+            self.file.seek(SeekFrom::Start(0))?;
+            self.file.write_all(buf.as_bytes())?;
+            self.file.sync_data()?;
+        }
+        Ok(removed)
+    }
+}
+
+fn check_field(what: &str, s: &str) -> io::Result<()> {
+    if s.contains(['\t', '\n', '\r']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} {:?} can't contain tabs or newlines", what, s),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_ledger(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("randr-ledger-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("ledger.tsv")
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_record_and_forget() {
+        let path = temp_ledger("record");
+        {
+            let mut ledger = Ledger::open(&path).unwrap();
+            ledger.record("envs", &strings(&["brave-fox", "calm-owl"])).unwrap();
+            ledger.record("hosts", &strings(&["lusab-babad"])).unwrap();
+        }
+
+        // Survives reopening.
+        let mut ledger = Ledger::open(&path).unwrap();
+        assert_eq!(
+            ledger.issued("envs").unwrap(),
+            HashSet::from(["brave-fox".to_string(), "calm-owl".to_string()])
+        );
+        let counts = ledger.namespaces().unwrap();
+        assert_eq!(counts.get("envs"), Some(&2));
+        assert_eq!(counts.get("hosts"), Some(&1));
+
+        assert_eq!(ledger.forget("envs", Some(&strings(&["calm-owl"]))).unwrap(), 1);
+        assert_eq!(ledger.issued("envs").unwrap().len(), 1);
+        assert_eq!(ledger.forget("envs", None).unwrap(), 1);
+        assert!(ledger.issued("envs").unwrap().is_empty());
+        assert_eq!(ledger.issued("hosts").unwrap().len(), 1);
+
+        assert!(ledger.record("bad\tns", &strings(&["x"])).is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers() {
+        let path = temp_ledger("concurrent");
+        Ledger::open(&path).unwrap();
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        let mut ledger = Ledger::open(&path).unwrap();
+                        let seen = ledger.issued("ns").unwrap().len();
+                        ledger.record("ns", &[format!("{}-{}", t, i)]).unwrap();
+                        assert_eq!(ledger.issued("ns").unwrap().len(), seen + 1);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let mut ledger = Ledger::open(&path).unwrap();
+        assert_eq!(ledger.entries().unwrap().len(), 200);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
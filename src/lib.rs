@@ -1,8 +1,19 @@
+use hmac::{Hmac, Mac};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
-use std::fmt;
+use rand::{Rng, SeedableRng};
+use sha1::Sha1;
+use std::collections::HashSet;
+use std::fmt::{self, Write as _};
+use std::net::Ipv4Addr;
+use std::ops::{Range, RangeInclusive};
+use std::str::FromStr;
+use std::sync::OnceLock;
 use uuid::Uuid;
 
+pub mod dirs;
+pub mod ledger;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RandomFormat {
     Uuid,
@@ -20,6 +31,11 @@ pub enum RandomFormat {
     ConstellationName,
     SportsReference,
     FoodCombination,
+    Pin,
+    TotpSecret,
+    Bytes,
+    Pronounceable,
+    Proquint,
 }
 
 impl RandomFormat {
@@ -40,9 +56,47 @@ impl RandomFormat {
             RandomFormat::ConstellationName => 21,
             RandomFormat::SportsReference => 15,
             RandomFormat::FoodCombination => 22,
+            RandomFormat::Pin => {
+                static DEFAULT: OnceLock<PinEntropy> = OnceLock::new();
+                DEFAULT.get_or_init(|| pin_entropy(PIN_LENGTH)).filtered as u32
+            }
+            RandomFormat::TotpSecret => TOTP_SECRET_BYTES as u32 * 8,
+            RandomFormat::Bytes => BYTES_LENGTH as u32 * 8,
+            RandomFormat::Pronounceable => pronounceable_entropy(SYLLABLES) as u32,
+            RandomFormat::Proquint => 32,
+        }
+    }
+
+    /// Default output length for formats that take one; `None` for
+    /// fixed-shape and wordlist formats.
+    pub fn default_length(&self) -> Option<usize> {
+        match self {
+            RandomFormat::UrlSafe => Some(16),
+            RandomFormat::ApiKey => Some(24),
+            RandomFormat::Pin => Some(PIN_LENGTH),
+            RandomFormat::TotpSecret => Some(TOTP_SECRET_BYTES),
+            RandomFormat::Bytes => Some(BYTES_LENGTH),
+            RandomFormat::Pronounceable => Some(SYLLABLES),
+            _ => None,
         }
     }
 
+    /// Rendered through a `Style`, so a `Constraint` shapes it. The others
+    /// (IDs, tokens, encodings) keep their own fixed alphabet.
+    pub fn is_styled(&self) -> bool {
+        !matches!(
+            self,
+            RandomFormat::Uuid
+                | RandomFormat::UuidV7
+                | RandomFormat::UrlSafe
+                | RandomFormat::ApiKey
+                | RandomFormat::Pin
+                | RandomFormat::TotpSecret
+                | RandomFormat::Bytes
+                | RandomFormat::Proquint
+        )
+    }
+
     pub fn short_name(&self) -> &'static str {
         match self {
             RandomFormat::Uuid => "uuid",
@@ -60,32 +114,202 @@ impl RandomFormat {
             RandomFormat::ConstellationName => "constellation",
             RandomFormat::SportsReference => "sports",
             RandomFormat::FoodCombination => "food",
+            RandomFormat::Pin => "pin",
+            RandomFormat::TotpSecret => "totp",
+            RandomFormat::Bytes => "bytes",
+            RandomFormat::Pronounceable => "word",
+            RandomFormat::Proquint => "proquint",
         }
     }
 
+    /// Other names accepted when parsing. None of them collide with a
+    /// short name or a menu theme.
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            RandomFormat::Uuid => &["uuid4", "uuidv4", "v4"],
+            RandomFormat::UuidV7 => &["uuid7", "v7"],
+            RandomFormat::UrlSafe => &["urlsafe"],
+            RandomFormat::ApiKey => &["apikey", "key"],
+            RandomFormat::MemorableName => &["adj-noun"],
+            RandomFormat::HistoricalFigure => &["history"],
+            RandomFormat::GeographicName => &["geographic"],
+            RandomFormat::CharacterName => &[],
+            RandomFormat::PhoneticAlphabet => &["nato"],
+            RandomFormat::RhymingPair => &["rhyming"],
+            RandomFormat::MusicalTerm => &["musical"],
+            RandomFormat::ScientificElement => &["chemical"],
+            RandomFormat::ConstellationName => &["stars"],
+            RandomFormat::SportsReference => &["sport"],
+            RandomFormat::FoodCombination => &["dish"],
+            RandomFormat::Pin => &[],
+            RandomFormat::TotpSecret => &["otp-secret"],
+            RandomFormat::Bytes => &[],
+            RandomFormat::Pronounceable => &["pronounceable"],
+            RandomFormat::Proquint => &[],
+        }
+    }
+
+    /// Exact lookup by short name or alias, ignoring case.
+    pub fn from_name(name: &str) -> Option<RandomFormat> {
+        let wanted = name.trim().to_lowercase();
+        RandomFormat::all()
+            .into_iter()
+            .find(|f| f.short_name() == wanted || f.aliases().contains(&wanted.as_str()))
+    }
+
+    /// The format whose name is closest to `name` by edit distance, if any
+    /// is close enough to be a plausible typo.
+    pub fn closest(name: &str) -> Option<RandomFormat> {
+        let wanted = name.trim().to_lowercase();
+        let wanted = wanted.as_str();
+        let limit = std::cmp::max(2, wanted.chars().count() / 3);
+        RandomFormat::all()
+            .into_iter()
+            .flat_map(|f| {
+                let names = std::iter::once(f.short_name()).chain(f.aliases().iter().copied());
+                names.map(move |n| (edit_distance(wanted, n), f))
+            })
+            .filter(|(distance, _)| *distance <= limit)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, f)| f)
+    }
+
     pub fn all() -> Vec<RandomFormat> {
-        let mut formats = vec![
-            RandomFormat::Uuid,
-            RandomFormat::UuidV7,
-            RandomFormat::UrlSafe,
-            RandomFormat::ApiKey,
-            RandomFormat::MemorableName,
-            RandomFormat::HistoricalFigure,
-            RandomFormat::GeographicName,
-            RandomFormat::CharacterName,
-            RandomFormat::PhoneticAlphabet,
-            RandomFormat::RhymingPair,
-            RandomFormat::MusicalTerm,
-            RandomFormat::ScientificElement,
-            RandomFormat::ConstellationName,
-            RandomFormat::SportsReference,
-            RandomFormat::FoodCombination,
-        ];
+        let mut formats = ALL_FORMATS.to_vec();
         formats.sort_by_key(|f| std::cmp::Reverse(f.entropy()));
         formats
     }
 }
 
+const ALL_FORMATS: [RandomFormat; 20] = [
+    RandomFormat::Uuid,
+    RandomFormat::UuidV7,
+    RandomFormat::UrlSafe,
+    RandomFormat::ApiKey,
+    RandomFormat::MemorableName,
+    RandomFormat::HistoricalFigure,
+    RandomFormat::GeographicName,
+    RandomFormat::CharacterName,
+    RandomFormat::PhoneticAlphabet,
+    RandomFormat::RhymingPair,
+    RandomFormat::MusicalTerm,
+    RandomFormat::ScientificElement,
+    RandomFormat::ConstellationName,
+    RandomFormat::SportsReference,
+    RandomFormat::FoodCombination,
+    RandomFormat::Pin,
+    RandomFormat::TotpSecret,
+    RandomFormat::Bytes,
+    RandomFormat::Pronounceable,
+    RandomFormat::Proquint,
+];
+
+/// Short names as values, aliases as hidden aliases, entropy as help — so
+/// `RandomFormat` can be a clap argument and shells complete it. Parsing
+/// this way is exact; use `FromStr` for prefixes and suggestions.
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for RandomFormat {
+    fn value_variants<'a>() -> &'a [RandomFormat] {
+        &ALL_FORMATS
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(
+            clap::builder::PossibleValue::new(self.short_name())
+                .aliases(self.aliases().iter().copied())
+                .help(format!("{} bits", self.entropy())),
+        )
+    }
+}
+
+/// Why a name didn't parse as a `RandomFormat`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseFormatError {
+    /// A prefix of more than one format's names.
+    Ambiguous {
+        input: String,
+        candidates: Vec<RandomFormat>,
+    },
+    Unknown {
+        input: String,
+        suggestion: Option<RandomFormat>,
+    },
+}
+
+impl fmt::Display for ParseFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFormatError::Ambiguous { input, candidates } => {
+                let names: Vec<&str> = candidates.iter().map(|c| c.short_name()).collect();
+                write!(f, "{:?} could be any of: {}", input, names.join(", "))
+            }
+            ParseFormatError::Unknown {
+                input,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "unknown format {:?} (did you mean {}?)",
+                input,
+                suggestion.short_name()
+            ),
+            ParseFormatError::Unknown { input, .. } => write!(f, "unknown format {:?}", input),
+        }
+    }
+}
+
+impl std::error::Error for ParseFormatError {}
+
+/// Short names and aliases, ignoring case; then unambiguous prefixes of
+/// them (`const` for `constellation`). Failing that, the error carries the
+/// closest name as a suggestion.
+impl FromStr for RandomFormat {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<RandomFormat, ParseFormatError> {
+        if let Some(format) = RandomFormat::from_name(s) {
+            return Ok(format);
+        }
+        let wanted = s.trim().to_lowercase();
+        let candidates: Vec<RandomFormat> = if wanted.is_empty() {
+            Vec::new()
+        } else {
+            RandomFormat::all()
+                .into_iter()
+                .filter(|f| {
+                    f.short_name().starts_with(&wanted)
+                        || f.aliases().iter().any(|a| a.starts_with(&wanted))
+                })
+                .collect()
+        };
+        match candidates[..] {
+            [format] => Ok(format),
+            [] => Err(ParseFormatError::Unknown {
+                input: s.to_string(),
+                suggestion: RandomFormat::closest(s),
+            }),
+            _ => Err(ParseFormatError::Ambiguous {
+                input: s.to_string(),
+                candidates,
+            }),
+        }
+    }
+}
+
+/// Levenshtein distance, by characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            row[j + 1] = substitute.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
 impl fmt::Display for RandomFormat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -104,6 +328,11 @@ impl fmt::Display for RandomFormat {
             RandomFormat::ConstellationName => write!(f, "CONSTELLATION ({})", self.entropy()),
             RandomFormat::SportsReference => write!(f, "SPORTS ({})", self.entropy()),
             RandomFormat::FoodCombination => write!(f, "FOOD ({})", self.entropy()),
+            RandomFormat::Pin => write!(f, "PIN ({})", self.entropy()),
+            RandomFormat::TotpSecret => write!(f, "TOTP ({})", self.entropy()),
+            RandomFormat::Bytes => write!(f, "BYTES ({})", self.entropy()),
+            RandomFormat::Pronounceable => write!(f, "WORD ({})", self.entropy()),
+            RandomFormat::Proquint => write!(f, "PROQUINT ({})", self.entropy()),
         }
     }
 }
@@ -206,65 +435,386 @@ const FOODS: &[&str] = &[
     "syrup", "honey", "jam",
 ];
 
+/// Codes people pick for themselves. `pin` rejects these on top of repeats
+/// and straight runs, so they never come back as a "random" PIN.
+const COMMON_PINS: &[&str] = &[
+    "1212", "1004", "2000", "6969", "1122", "1313", "2001", "1010", "2580", "0852",
+    "1984", "1986", "2020", "4545", "5683", "0007", "1357", "2468", "123123", "121212",
+    "112233", "123321", "159753", "147258", "696969", "252525", "101010", "131313",
+];
+
+// Syllable parts for `pronounceable`. Every syllable opens with exactly one
+// onset consonant and finals are consonants too, so a word splits back into
+// syllables only one way — distinct syllable sequences give distinct words.
+const ONSETS: &[char] = &[
+    'b', 'd', 'f', 'g', 'h', 'j', 'k', 'l', 'm', 'n', 'p', 'r', 's', 't', 'v', 'z',
+];
+const VOWELS: &[char] = &['a', 'e', 'i', 'o', 'u'];
+const FINALS: &[char] = &['l', 'm', 'n', 'r', 's'];
+
+/// Chance a syllable is closed (CVC) rather than open (CV). Kept low so
+/// words read like `tavoreni` rather than `tanvorsen`.
+const CLOSED_SYLLABLE_P: f64 = 0.2;
+
 // ---- Primitive helpers --------------------------------------------------
 
-fn from_charset(length: usize, charset: &str) -> String {
-    let mut rng = rand::thread_rng();
-    (0..length)
-        .map(|_| {
-            let idx = rng.gen_range(0..charset.len());
-            charset.chars().nth(idx).unwrap()
-        })
-        .collect()
+const URL_SAFE_CHARS: &[u8; 64] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_";
+const API_KEY_CHARS: &[u8; 36] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const DIGITS: &[u8; 10] = b"0123456789";
+
+/// Append `length` characters drawn uniformly from the ASCII `charset`, a
+/// random byte each. Bytes past the last whole multiple of `N` are redrawn
+/// so every character is equally likely; with `N` a constant, picking one
+/// is a compare and a multiply.
+fn push_charset<const N: usize, R: Rng + ?Sized>(
+    out: &mut String,
+    length: usize,
+    charset: &[u8; N],
+    rng: &mut R,
+) {
+    const { assert!(N > 0 && N <= 256) };
+    let zone = 256 / N * N;
+    let mut bytes = [0u8; 64];
+    out.reserve(length);
+    let mut left = length;
+    while left > 0 {
+        // A few spare bytes, so a redraw rarely needs another call.
+        let batch = (left + left / 8 + 1).min(bytes.len());
+        rng.fill_bytes(&mut bytes[..batch]);
+        // Map the kept bytes to characters in place, packed to the front.
+        let mut kept = 0;
+        for i in 0..batch {
+            if kept == left {
+                break;
+            }
+            let b = bytes[i] as usize;
+            if b < zone {
+                bytes[kept] = charset[b % N];
+                kept += 1;
+            }
+        }
+        out.push_str(std::str::from_utf8(&bytes[..kept]).expect("charsets are ASCII"));
+        left -= kept;
+    }
+}
+
+fn from_charset<const N: usize, R: Rng + ?Sized>(
+    length: usize,
+    charset: &[u8; N],
+    rng: &mut R,
+) -> String {
+    let mut out = String::with_capacity(length);
+    push_charset(&mut out, length, charset, rng);
+    out
+}
+
+fn random_bytes<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    fill_random_bytes(&mut bytes, n, rng);
+    bytes
+}
+
+/// Refill `bytes` with `n` random bytes, keeping its allocation.
+fn fill_random_bytes<R: Rng + ?Sized>(bytes: &mut Vec<u8>, n: usize, rng: &mut R) {
+    bytes.clear();
+    bytes.resize(n, 0);
+    rng.fill_bytes(bytes);
 }
 
-fn random_item<T: Clone>(items: &[T]) -> T {
-    let mut rng = rand::thread_rng();
+fn random_item<T: Clone, R: Rng + ?Sized>(items: &[T], rng: &mut R) -> T {
     items[rng.gen_range(0..items.len())].clone()
 }
 
-fn capitalize(s: &str) -> String {
-    let mut chars = s.chars();
-    match chars.next() {
-        Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
-        None => String::new(),
-    }
-}
-
-fn leetify_str<R: Rng>(s: &str, rng: &mut R) -> String {
-    s.chars()
-        .map(|c| {
-            let sub = match c.to_ascii_lowercase() {
-                'a' => Some('4'),
-                'e' => Some('3'),
-                'i' => Some('1'),
-                'o' => Some('0'),
-                's' => Some('5'),
-                't' => Some('7'),
-                'g' => Some('9'),
-                'l' => Some('1'),
-                'b' => Some('8'),
-                _ => None,
-            };
-            match sub {
-                Some(d) if rng.gen_bool(0.5) => d,
-                _ => c,
+/// Append `word` in `case`. ASCII words (every built-in list) skip the
+/// Unicode case tables.
+fn push_cased(out: &mut String, word: &str, case: Case) {
+    match case {
+        Case::Lower if word.is_ascii() => {
+            out.extend(word.bytes().map(|b| b.to_ascii_lowercase() as char))
+        }
+        Case::Upper if word.is_ascii() => {
+            out.extend(word.bytes().map(|b| b.to_ascii_uppercase() as char))
+        }
+        Case::Lower => out.push_str(&word.to_lowercase()),
+        Case::Upper => out.push_str(&word.to_uppercase()),
+        Case::Title => {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                out.extend(first.to_uppercase());
+                out.push_str(chars.as_str());
+            }
+        }
+    }
+}
+
+/// Swap each leetable letter for its digit with even odds, in place.
+/// Leet `text` from byte `from` on.
+fn leetify<R: Rng + ?Sized>(text: &mut String, from: usize, rng: &mut R) {
+    let mut bytes = std::mem::take(text).into_bytes();
+    for b in bytes[from..].iter_mut() {
+        let sub = match b.to_ascii_lowercase() {
+            b'a' => b'4',
+            b'e' => b'3',
+            b'i' => b'1',
+            b'o' => b'0',
+            b's' => b'5',
+            b't' => b'7',
+            b'g' => b'9',
+            b'l' => b'1',
+            b'b' => b'8',
+            _ => continue,
+        };
+        if rng.gen_bool(0.5) {
+            *b = sub;
+        }
+    }
+    *text = String::from_utf8(bytes).expect("leet only swaps ASCII for ASCII");
+}
+
+// ---- Encoding -----------------------------------------------------------
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// RFC 4648 base32 without `=` padding — the form authenticator apps expect
+/// in `otpauth://` secrets.
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8).div_ceil(5));
+    push_base32(&mut out, data);
+    out
+}
+
+fn push_base32(out: &mut String, data: &[u8]) {
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+}
+
+/// Inverse of `base32_encode`. Accepts lowercase, whitespace and trailing
+/// `=` padding (as people paste secrets); `None` on any other character.
+pub fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    let mut len = 0;
+    for c in s.trim_end_matches('=').chars().filter(|c| !c.is_whitespace()) {
+        if !c.is_ascii() {
+            return None;
+        }
+        len += 1;
+        let upper = c.to_ascii_uppercase() as u8;
+        let value = BASE32_ALPHABET.iter().position(|&a| a == upper)?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    // A final block of 1, 3 or 6 characters can't come from whole bytes.
+    // Bits past the last whole byte are padding and must be zero, so each
+    // byte string has exactly one encoding.
+    if matches!(len % 8, 1 | 3 | 6) || buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE58_ALPHABET: &[u8; 58] =
+    b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// How `bytes` turns raw random bytes into text. Each one round-trips to
+/// exactly the drawn bytes, unlike the sampled-charset formats. Hex unless
+/// asked otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ByteEncoding {
+    #[default]
+    Hex,
+    Base64,
+    Base64Url,
+    Base64UrlNoPad,
+    Base32,
+    Base58,
+}
+
+impl ByteEncoding {
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            ByteEncoding::Hex => "hex",
+            ByteEncoding::Base64 => "base64",
+            ByteEncoding::Base64Url => "base64url",
+            ByteEncoding::Base64UrlNoPad => "base64url-nopad",
+            ByteEncoding::Base32 => "base32",
+            ByteEncoding::Base58 => "base58",
+        }
+    }
+
+    pub fn all() -> Vec<ByteEncoding> {
+        vec![
+            ByteEncoding::Hex,
+            ByteEncoding::Base64,
+            ByteEncoding::Base64Url,
+            ByteEncoding::Base64UrlNoPad,
+            ByteEncoding::Base32,
+            ByteEncoding::Base58,
+        ]
+    }
+}
+
+fn push_base64(out: &mut String, data: &[u8], alphabet: &[u8; 64], pad: bool) {
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        let emitted = chunk.len() + 1;
+        for i in 0..4 {
+            if i < emitted {
+                out.push(alphabet[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+}
+
+/// `digits` is scratch space for the base-58 digits, kept between calls.
+fn push_base58(out: &mut String, data: &[u8], digits: &mut Vec<u8>) {
+    // Repeated division of the big-endian number by 58; each leading zero
+    // byte becomes a leading '1', as in Bitcoin addresses.
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    digits.clear();
+    for &byte in &data[zeros..] {
+        let mut carry = byte as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    out.extend(std::iter::repeat_n('1', zeros));
+    out.extend(digits.iter().rev().map(|&d| BASE58_ALPHABET[d as usize] as char));
+}
+
+pub fn encode_bytes(data: &[u8], encoding: ByteEncoding) -> String {
+    let mut out = String::new();
+    push_encoded(&mut out, data, encoding, &mut Vec::new());
+    out
+}
+
+/// Append `data` to `out` in `encoding`; `scratch` is only used by base58.
+fn push_encoded(out: &mut String, data: &[u8], encoding: ByteEncoding, scratch: &mut Vec<u8>) {
+    match encoding {
+        ByteEncoding::Hex => out.extend(data.iter().flat_map(|&b| {
+            [HEX_DIGITS[(b >> 4) as usize] as char, HEX_DIGITS[(b & 0xf) as usize] as char]
+        })),
+        ByteEncoding::Base64 => push_base64(out, data, BASE64_ALPHABET, true),
+        ByteEncoding::Base64Url => push_base64(out, data, BASE64URL_ALPHABET, true),
+        ByteEncoding::Base64UrlNoPad => push_base64(out, data, BASE64URL_ALPHABET, false),
+        ByteEncoding::Base32 => push_base32(out, data),
+        ByteEncoding::Base58 => push_base58(out, data, scratch),
+    }
+}
+
+// Proquints (https://arxiv.org/html/0901.4016): each 16 bits becomes a
+// consonant-vowel-consonant-vowel-consonant "quint", 4+2+4+2+4 bits.
+const PROQUINT_CONSONANTS: &[u8; 16] = b"bdfghjklmnprstvz";
+const PROQUINT_VOWELS: &[u8; 4] = b"aiou";
+
+fn encode_quint(word: u16, out: &mut String) {
+    let w = word as usize;
+    out.push(PROQUINT_CONSONANTS[(w >> 12) & 0xf] as char);
+    out.push(PROQUINT_VOWELS[(w >> 10) & 0x3] as char);
+    out.push(PROQUINT_CONSONANTS[(w >> 6) & 0xf] as char);
+    out.push(PROQUINT_VOWELS[(w >> 4) & 0x3] as char);
+    out.push(PROQUINT_CONSONANTS[w & 0xf] as char);
+}
+
+fn decode_quint(quint: &str) -> Option<u16> {
+    let bytes = quint.as_bytes();
+    if bytes.len() != 5 {
+        return None;
+    }
+    let mut word: u16 = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let b = b.to_ascii_lowercase();
+        word = if i % 2 == 0 {
+            (word << 4) | PROQUINT_CONSONANTS.iter().position(|&c| c == b)? as u16
+        } else {
+            (word << 2) | PROQUINT_VOWELS.iter().position(|&v| v == b)? as u16
+        };
+    }
+    Some(word)
+}
+
+/// Encode a 32-bit value as a proquint, e.g. `0x7f000001` → `lusab-babad`.
+pub fn proquint_encode(value: u32) -> String {
+    let mut out = String::with_capacity(11);
+    push_proquint(&mut out, value);
+    out
+}
+
+fn push_proquint(out: &mut String, value: u32) {
+    encode_quint((value >> 16) as u16, out);
+    out.push('-');
+    encode_quint(value as u16, out);
+}
+
+/// Inverse of `proquint_encode`. Case-insensitive; `None` unless the input
+/// is exactly two quints joined by `-`.
+pub fn proquint_decode(s: &str) -> Option<u32> {
+    let (hi, lo) = s.split_once('-')?;
+    Some((decode_quint(hi)? as u32) << 16 | decode_quint(lo)? as u32)
+}
+
+pub fn proquint_from_ipv4(addr: Ipv4Addr) -> String {
+    proquint_encode(u32::from(addr))
+}
+
+pub fn ipv4_from_proquint(s: &str) -> Option<Ipv4Addr> {
+    proquint_decode(s).map(Ipv4Addr::from)
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
             }
+            _ => format!("%{:02X}", b),
         })
         .collect()
 }
 
 // ---- Style --------------------------------------------------------------
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Case {
     Lower,
     Title,
     Upper,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum TagKind {
+/// Shape of the random tag mixed into styled names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagKind {
     SmallInt,
     Alnum(usize),
     Alpha(usize),
@@ -272,6 +822,29 @@ enum TagKind {
     Digits(usize),
 }
 
+impl TagKind {
+    /// Every shape `Style::random` can roll.
+    pub fn all() -> Vec<TagKind> {
+        let mut kinds = vec![TagKind::SmallInt];
+        kinds.extend((2..=5).map(TagKind::Alnum));
+        kinds.extend((2..=4).map(TagKind::Alpha));
+        kinds.extend((3..=6).map(TagKind::Hex));
+        kinds.extend((2..=5).map(TagKind::Digits));
+        kinds
+    }
+
+    /// `smallint`, `digits5`, `hex4`, ...
+    pub fn short_name(&self) -> String {
+        match self {
+            TagKind::SmallInt => "smallint".to_string(),
+            TagKind::Alnum(len) => format!("alnum{}", len),
+            TagKind::Alpha(len) => format!("alpha{}", len),
+            TagKind::Hex(len) => format!("hex{}", len),
+            TagKind::Digits(len) => format!("digits{}", len),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum TagPosition {
     Prefix,
@@ -291,11 +864,20 @@ pub struct Style {
     tag_kind: TagKind,
     tag_position: TagPosition,
     leetify: bool,
+    constraint: Option<Constraint>,
+    /// Put in front of every value, and counted when checking it against
+    /// `constraint`.
+    prefix: String,
 }
 
 impl Style {
     pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
+        Style::random_with(&mut rand::thread_rng())
+    }
+
+    /// `random`, rolled from `rng`: a seeded `rng` always gives the same
+    /// style.
+    pub fn random_with<R: Rng>(rng: &mut R) -> Self {
         let case = match rng.gen_range(0..3) {
             0 => Case::Lower,
             1 => Case::Title,
@@ -306,8 +888,8 @@ impl Style {
         } else {
             &["-", "_", ".", ""]
         };
-        let body_sep = *body_sep_opts.choose(&mut rng).unwrap();
-        let tag_sep = *["-", "_", ".", ""].choose(&mut rng).unwrap();
+        let body_sep = *body_sep_opts.choose(rng).unwrap();
+        let tag_sep = *["-", "_", ".", ""].choose(rng).unwrap();
         let tag_kind = match rng.gen_range(0..5) {
             0 => TagKind::SmallInt,
             1 => TagKind::Alnum(rng.gen_range(2..=5)),
@@ -328,182 +910,1661 @@ impl Style {
             tag_kind,
             tag_position,
             leetify,
+            constraint: None,
+            prefix: String::new(),
         }
     }
-}
 
-fn render_styled(words: &[&str], style: &Style) -> String {
-    let mut rng = rand::thread_rng();
+    /// A random style restricted to choices that satisfy `constraint`.
+    /// Output rendered with it is also checked against the constraint, so
+    /// every value it produces is valid for that target.
+    pub fn for_target(constraint: Constraint) -> Self {
+        Style::for_target_with(constraint, &mut rand::thread_rng())
+    }
 
-    let cased: Vec<String> = words
-        .iter()
-        .map(|w| match style.case {
-            Case::Lower => w.to_lowercase(),
-            Case::Title => capitalize(w),
-            Case::Upper => w.to_uppercase(),
-        })
-        .collect();
+    /// `for_target`, rolled from `rng`.
+    pub fn for_target_with<R: Rng>(constraint: Constraint, rng: &mut R) -> Self {
+        let mut style = Style::random_with(rng);
+        constraint.restrict(&mut style, rng);
+        style.constraint = Some(constraint);
+        style
+    }
 
-    let tag = match style.tag_kind {
-        TagKind::SmallInt => {
-            let max = [9u32, 99, 999, 9999][rng.gen_range(0..4)];
-            rng.gen_range(1..=max).to_string()
+    /// The plainest style with a given tag: lowercase, `-` separators, tag
+    /// as suffix, no leet. What `Candidate` measures against.
+    fn plain(tag_kind: TagKind) -> Self {
+        Style {
+            case: Case::Lower,
+            body_sep: "-",
+            tag_sep: "-",
+            tag_kind,
+            tag_position: TagPosition::Suffix,
+            leetify: false,
+            constraint: None,
+            prefix: String::new(),
         }
-        TagKind::Alnum(len) => from_charset(len, "abcdefghijklmnopqrstuvwxyz0123456789"),
-        TagKind::Alpha(len) => from_charset(len, "abcdefghijklmnopqrstuvwxyz"),
-        TagKind::Hex(len) => from_charset(len, "0123456789abcdef"),
-        TagKind::Digits(len) => from_charset(len, "0123456789"),
-    };
+    }
 
-    let assembled = if cased.is_empty() {
-        tag
-    } else {
-        let pos = match style.tag_position {
-            TagPosition::Prefix => 0,
-            TagPosition::Middle if cased.len() <= 1 => cased.len(),
-            TagPosition::Middle => rng.gen_range(1..cased.len()),
-            TagPosition::Suffix => cased.len(),
-        };
-        if pos == 0 {
-            format!("{}{}{}", tag, style.tag_sep, cased.join(style.body_sep))
-        } else if pos == cased.len() {
-            format!("{}{}{}", cased.join(style.body_sep), style.tag_sep, tag)
-        } else {
-            let left = cased[..pos].join(style.body_sep);
-            let right = cased[pos..].join(style.body_sep);
-            format!(
-                "{}{}{}{}{}",
-                left, style.tag_sep, tag, style.tag_sep, right
-            )
-        }
-    };
+    /// Pin the tag shape instead of the rolled one.
+    pub fn with_tag(mut self, tag_kind: TagKind) -> Self {
+        self.tag_kind = tag_kind;
+        self
+    }
 
-    if style.leetify {
-        leetify_str(&assembled, &mut rng)
-    } else {
-        assembled
+    /// Start every value with `prefix` (e.g. `feature/`). Under a
+    /// constraint the prefix is checked once here, and each value is then
+    /// fitted and checked with the prefix in place, so none comes out
+    /// invalid or over the length limit.
+    pub fn with_prefix(mut self, prefix: &str) -> Result<Self, PrefixError> {
+        if let Some(constraint) = self.constraint {
+            if let Some(max) = constraint.rules().max_len {
+                if prefix.len() + MIN_PREFIX_ROOM > max {
+                    return Err(PrefixError::TooLong(constraint, max));
+                }
+            }
+            // Values start with a word or tag, so a prefix that can't be
+            // followed by a letter can't start any of them.
+            let starts = ["a", "A"].iter().any(|c| constraint.accepts(&format!("{prefix}{c}")));
+            if !starts {
+                return Err(PrefixError::Invalid(constraint));
+            }
+        }
+        self.prefix = prefix.to_string();
+        Ok(self)
     }
 }
 
-// ---- Generators (public: random style each call) ------------------------
+/// Characters `Style::with_prefix` leaves for the value itself under a
+/// length limit.
+const MIN_PREFIX_ROOM: usize = 8;
 
-pub fn uuid() -> String {
-    Uuid::new_v4().to_string()
+/// Why `Style::with_prefix` refused a prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    /// No value can follow it and be valid for the target.
+    Invalid(Constraint),
+    /// It leaves too little of the target's length limit (the `usize`) for
+    /// a value.
+    TooLong(Constraint, usize),
 }
 
-pub fn uuidv7() -> String {
-    Uuid::now_v7().to_string()
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrefixError::Invalid(constraint) => {
+                write!(f, "can't start a valid {} name", constraint.short_name())
+            }
+            PrefixError::TooLong(constraint, max) => write!(
+                f,
+                "leaves fewer than {} of the {} characters a {} name may have",
+                MIN_PREFIX_ROOM,
+                max,
+                constraint.short_name()
+            ),
+        }
+    }
 }
 
-pub fn url_safe(length: usize) -> String {
-    let charset = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_";
-    from_charset(length, charset)
+impl std::error::Error for PrefixError {}
+
+/// Which piece of a generated value a run of characters came from — enough
+/// to colour tags and words differently without re-parsing the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Word,
+    Tag,
+    Sep,
+    /// Formats without words and tags (uuid, api, ...), or text added after
+    /// generation such as `--prefix`.
+    Plain,
 }
 
-pub fn api_key(length: usize) -> String {
-    let charset = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    from_charset(length, charset)
+/// A generated value plus the byte range each `Part` covers in it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Styled {
+    pub text: String,
+    pub parts: Vec<(Part, Range<usize>)>,
 }
 
-fn pick_words(format: RandomFormat) -> Vec<&'static str> {
-    let mut rng = rand::thread_rng();
-    match format {
-        RandomFormat::MemorableName => vec![random_item(ADJECTIVES), random_item(NOUNS)],
-        RandomFormat::HistoricalFigure => vec![random_item(FIGURES)],
-        RandomFormat::GeographicName => vec![random_item(LOCATIONS)],
-        RandomFormat::CharacterName => vec![random_item(CHARACTERS)],
-        RandomFormat::PhoneticAlphabet => {
-            let count = rng.gen_range(2..=3);
-            (0..count).map(|_| random_item(PHONETICS)).collect()
+impl Styled {
+    fn push(&mut self, part: Part, s: &str) {
+        self.push_with(part, |text| text.push_str(s));
+    }
+
+    /// Let `write` append to the text, recording what it added as `part`.
+    fn push_with(&mut self, part: Part, write: impl FnOnce(&mut String)) {
+        let start = self.text.len();
+        write(&mut self.text);
+        if self.text.len() > start {
+            self.parts.push((part, start..self.text.len()));
         }
-        RandomFormat::RhymingPair => {
-            let (a, b) = random_item(RHYME_PAIRS);
-            vec![a, b]
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+        self.parts.clear();
+    }
+
+    /// Drop or shorten parts past the end of `text`, after a constraint
+    /// truncated it.
+    fn clip(&mut self) {
+        let len = self.text.len();
+        self.parts.retain(|(_, range)| range.start < len);
+        if let Some((_, range)) = self.parts.last_mut() {
+            range.end = range.end.min(len);
         }
-        RandomFormat::MusicalTerm => {
-            let count = rng.gen_range(1..=2);
-            (0..count).map(|_| random_item(MUSICAL)).collect()
+    }
+
+    /// Put `s` in front, as its own part.
+    pub fn prepend(&mut self, part: Part, s: &str) {
+        if s.is_empty() {
+            return;
         }
-        RandomFormat::ScientificElement => {
-            let count = rng.gen_range(1..=2);
-            (0..count).map(|_| random_item(ELEMENTS)).collect()
+        self.text.insert_str(0, s);
+        for (_, range) in self.parts.iter_mut() {
+            *range = range.start + s.len()..range.end + s.len();
         }
-        RandomFormat::ConstellationName => vec![random_item(CONSTELLATIONS)],
-        RandomFormat::SportsReference => vec![random_item(SPORTS)],
-        RandomFormat::FoodCombination => vec![random_item(FOOD_ADJECTIVES), random_item(FOODS)],
-        // Non-wordlist formats: caller shouldn't reach here.
-        _ => vec![],
+        self.parts.insert(0, (part, 0..s.len()));
     }
-}
 
-pub fn memorable_name() -> String {
-    render_styled(&pick_words(RandomFormat::MemorableName), &Style::random())
-}
-pub fn historical_figure() -> String {
-    render_styled(&pick_words(RandomFormat::HistoricalFigure), &Style::random())
-}
-pub fn geographic_name() -> String {
-    render_styled(&pick_words(RandomFormat::GeographicName), &Style::random())
-}
-pub fn character_name() -> String {
-    render_styled(&pick_words(RandomFormat::CharacterName), &Style::random())
-}
-pub fn phonetic_alphabet() -> String {
-    render_styled(&pick_words(RandomFormat::PhoneticAlphabet), &Style::random())
-}
-pub fn rhyming_pair() -> String {
-    render_styled(&pick_words(RandomFormat::RhymingPair), &Style::random())
-}
-pub fn musical_term() -> String {
-    render_styled(&pick_words(RandomFormat::MusicalTerm), &Style::random())
-}
-pub fn scientific_element() -> String {
-    render_styled(&pick_words(RandomFormat::ScientificElement), &Style::random())
+    /// The text, piece by piece.
+    pub fn spans(&self) -> impl Iterator<Item = (Part, &str)> {
+        self.parts.iter().map(|(part, range)| (*part, &self.text[range.clone()]))
+    }
 }
-pub fn constellation_name() -> String {
-    render_styled(&pick_words(RandomFormat::ConstellationName), &Style::random())
+
+impl From<String> for Styled {
+    fn from(text: String) -> Styled {
+        let mut styled = Styled::default();
+        styled.push(Part::Plain, &text);
+        styled
+    }
 }
-pub fn sports_reference() -> String {
-    render_styled(&pick_words(RandomFormat::SportsReference), &Style::random())
+
+/// Render caller-supplied words — a custom wordlist — in `style`, the way
+/// the built-in word formats are rendered. Under a constraint, words it
+/// can't take as given are folded to ASCII letters and digits (`café` →
+/// `cafe`); if that still doesn't fit, it's an error.
+pub fn render_words(words: &[&str], style: &Style) -> Result<Styled, RenderError> {
+    let mut out = Styled::default();
+    render_into(words, style, &mut rand::thread_rng(), &mut out)?;
+    Ok(out)
 }
-pub fn food_combination() -> String {
-    render_styled(&pick_words(RandomFormat::FoodCombination), &Style::random())
+
+/// The words can't be rendered into something `Constraint` accepts, even
+/// folded to ASCII.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderError(pub Constraint);
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "these words can't be made valid for --for {}", self.0.short_name())
+    }
 }
 
-// ---- Top-level entry points --------------------------------------------
+impl std::error::Error for RenderError {}
 
-pub fn generate(format: RandomFormat) -> String {
-    generate_with_style(format, &Style::random())
+/// Renders tried before giving up on a set of words. The style was
+/// narrowed to valid choices up front, so retries only catch a tag or leet
+/// roll the narrowing couldn't rule out; words that fail this many times
+/// won't ever pass.
+const MAX_RENDER_ATTEMPTS: usize = 100;
+
+const BUILT_IN_WORDS_FIT: &str = "built-in words are ASCII letters, valid for every constraint";
+
+/// `render_words` into `out`, replacing what it held.
+fn render_into<R: Rng + ?Sized>(
+    words: &[&str],
+    style: &Style,
+    rng: &mut R,
+    out: &mut Styled,
+) -> Result<(), RenderError> {
+    let Some(constraint) = style.constraint else {
+        out.clear();
+        out.push(Part::Plain, &style.prefix);
+        render_unconstrained(words, style, rng, out);
+        return Ok(());
+    };
+    if render_constrained(words, style, constraint, rng, out) {
+        return Ok(());
+    }
+    let folded: Vec<String> =
+        words.iter().map(|word| fold_ascii(word)).filter(|word| !word.is_empty()).collect();
+    let folded: Vec<&str> = folded.iter().map(String::as_str).collect();
+    if !folded.is_empty() && render_constrained(&folded, style, constraint, rng, out) {
+        return Ok(());
+    }
+    out.clear();
+    Err(RenderError(constraint))
 }
 
-/// Generate using a caller-provided style — use this when producing a batch
-/// of items that should share visual shape (one section of the menu, or a
-/// `randr <format>` listing).
-pub fn generate_with_style(format: RandomFormat, style: &Style) -> String {
-    match format {
-        RandomFormat::Uuid => uuid(),
-        RandomFormat::UuidV7 => uuidv7(),
-        RandomFormat::UrlSafe => url_safe(16),
-        RandomFormat::ApiKey => api_key(24),
-        _ => render_styled(&pick_words(format), style),
+fn render_constrained<R: Rng + ?Sized>(
+    words: &[&str],
+    style: &Style,
+    constraint: Constraint,
+    rng: &mut R,
+    out: &mut Styled,
+) -> bool {
+    for _ in 0..MAX_RENDER_ATTEMPTS {
+        out.clear();
+        out.push(Part::Plain, &style.prefix);
+        render_unconstrained(words, style, rng, out);
+        out.text = constraint.fit(std::mem::take(&mut out.text));
+        if constraint.accepts(&out.text) {
+            out.clip();
+            return true;
+        }
     }
+    false
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Accented Latin letters and what they fold to.
+const ASCII_FOLDS: &[(&str, &str)] = &[
+    ("àáâãäåāăą", "a"),
+    ("çćĉċč", "c"),
+    ("ďđð", "d"),
+    ("èéêëēĕėęě", "e"),
+    ("ĝğġģ", "g"),
+    ("ĥħ", "h"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ĵ", "j"),
+    ("ķ", "k"),
+    ("ĺļľŀł", "l"),
+    ("ñńņňŉ", "n"),
+    ("òóôõöøōŏő", "o"),
+    ("ŕŗř", "r"),
+    ("śŝşš", "s"),
+    ("ţťŧ", "t"),
+    ("ùúûüũūŭůűų", "u"),
+    ("ŵ", "w"),
+    ("ýÿŷ", "y"),
+    ("źżž", "z"),
+    ("ß", "ss"),
+    ("æ", "ae"),
+    ("œ", "oe"),
+    ("þ", "th"),
+];
 
-    #[test]
-    fn test_uuid() {
-        let s = uuid();
-        assert_eq!(s.len(), 36);
-        assert!(s.chars().all(|c| c.is_ascii_hexdigit() || c == '-'));
+/// `word` with accents folded off and anything else that isn't an ASCII
+/// letter or digit dropped; `Style` re-cases it, so case isn't kept.
+fn fold_ascii(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if let Some((_, to)) = ASCII_FOLDS.iter().find(|(from, _)| from.contains(c)) {
+            out.push_str(to);
+        }
     }
+    out
+}
 
-    #[test]
-    fn test_uuidv7() {
-        let s = uuidv7();
-        assert_eq!(s.len(), 36);
+fn push_tag<R: Rng + ?Sized>(out: &mut String, tag_kind: TagKind, rng: &mut R) {
+    match tag_kind {
+        TagKind::SmallInt => {
+            let max = [9u32, 99, 999, 9999][rng.gen_range(0..4)];
+            write!(out, "{}", rng.gen_range(1..=max)).unwrap();
+        }
+        TagKind::Alnum(len) => push_charset(out, len, b"abcdefghijklmnopqrstuvwxyz0123456789", rng),
+        TagKind::Alpha(len) => push_charset(out, len, b"abcdefghijklmnopqrstuvwxyz", rng),
+        TagKind::Hex(len) => push_charset(out, len, b"0123456789abcdef", rng),
+        TagKind::Digits(len) => push_charset(out, len, DIGITS, rng),
+    }
+}
+
+fn render_unconstrained<R: Rng + ?Sized>(
+    words: &[&str],
+    style: &Style,
+    rng: &mut R,
+    out: &mut Styled,
+) {
+    let start = out.text.len();
+    let pos = match style.tag_position {
+        TagPosition::Prefix => 0,
+        TagPosition::Middle if words.len() <= 1 => words.len(),
+        TagPosition::Middle => rng.gen_range(1..words.len()),
+        TagPosition::Suffix => words.len(),
+    };
+    for (i, word) in words.iter().enumerate() {
+        if i == pos {
+            if i > 0 {
+                out.push(Part::Sep, style.tag_sep);
+            }
+            out.push_with(Part::Tag, |text| push_tag(text, style.tag_kind, rng));
+            out.push(Part::Sep, style.tag_sep);
+        } else if i > 0 {
+            out.push(Part::Sep, style.body_sep);
+        }
+        out.push_with(Part::Word, |text| push_cased(text, word, style.case));
+    }
+    if pos == words.len() {
+        if !words.is_empty() {
+            out.push(Part::Sep, style.tag_sep);
+        }
+        out.push_with(Part::Tag, |text| push_tag(text, style.tag_kind, rng));
+    }
+
+    // Leetspeak swaps ASCII letters for ASCII digits, so the ranges hold.
+    if style.leetify {
+        leetify(&mut out.text, start, rng);
+    }
+}
+
+// ---- Target constraints -------------------------------------------------
+
+/// A system the output has to be valid for. Restricts which `Style`
+/// choices get rolled and filters what `render_words` produces. Formats
+/// that aren't `is_styled` (uuid, api, ...) can't be shaped by one, so the
+/// CLI refuses `--for` with them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Constraint {
+    Dns,
+    GitBranch,
+    RustIdent,
+    PythonIdent,
+    JsIdent,
+    SqlTable,
+    EnvVar,
+    Filename,
+    EmailLocal,
+}
+
+/// Which choices of each `Style` axis a target allows.
+struct Rules {
+    cases: &'static [Case],
+    body_seps: &'static [&'static str],
+    tag_seps: &'static [&'static str],
+    leetify: bool,
+    /// Prefix tags may start the value with a digit.
+    leading_digit: bool,
+    /// Letter tags would break a case-sensitive target (e.g. `UPPER_SNAKE`).
+    numeric_tags: bool,
+    max_len: Option<usize>,
+}
+
+const ALL_CASES: &[Case] = &[Case::Lower, Case::Title, Case::Upper];
+
+impl Constraint {
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Constraint::Dns => "dns",
+            Constraint::GitBranch => "git-branch",
+            Constraint::RustIdent => "rust",
+            Constraint::PythonIdent => "python",
+            Constraint::JsIdent => "js",
+            Constraint::SqlTable => "sql",
+            Constraint::EnvVar => "env",
+            Constraint::Filename => "filename",
+            Constraint::EmailLocal => "email",
+        }
+    }
+
+    pub fn all() -> Vec<Constraint> {
+        vec![
+            Constraint::Dns,
+            Constraint::GitBranch,
+            Constraint::RustIdent,
+            Constraint::PythonIdent,
+            Constraint::JsIdent,
+            Constraint::SqlTable,
+            Constraint::EnvVar,
+            Constraint::Filename,
+            Constraint::EmailLocal,
+        ]
+    }
+
+    pub fn accepts(&self, s: &str) -> bool {
+        match self {
+            Constraint::Dns => is_dns_label(s),
+            Constraint::GitBranch => is_git_branch_name(s),
+            Constraint::RustIdent => {
+                is_identifier(s, |_| false) && s != "_" && !RUST_KEYWORDS.contains(&s)
+            }
+            Constraint::PythonIdent => is_identifier(s, |_| false) && !PYTHON_KEYWORDS.contains(&s),
+            Constraint::JsIdent => is_identifier(s, |c| c == '$') && !JS_RESERVED.contains(&s),
+            Constraint::SqlTable => {
+                s.len() <= SQL_IDENT_MAX
+                    && s.starts_with(|c: char| c.is_ascii_lowercase())
+                    && s.chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                    && !SQL_RESERVED.contains(&s)
+            }
+            Constraint::EnvVar => {
+                s.starts_with(|c: char| c.is_ascii_uppercase())
+                    && s.chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            }
+            Constraint::Filename => is_portable_filename(s),
+            Constraint::EmailLocal => is_email_local_part(s),
+        }
+    }
+
+    fn rules(&self) -> Rules {
+        match self {
+            Constraint::Dns => Rules {
+                cases: &[Case::Lower],
+                body_seps: &["-"],
+                tag_seps: &["-", ""],
+                leetify: false,
+                leading_digit: false,
+                numeric_tags: false,
+                max_len: Some(DNS_LABEL_MAX),
+            },
+            // `.` is legal mid-name but invites `..` and `.lock` endings.
+            Constraint::GitBranch => Rules {
+                cases: ALL_CASES,
+                body_seps: &["-", "_", ""],
+                tag_seps: &["-", "_", ""],
+                leetify: true,
+                leading_digit: true,
+                numeric_tags: false,
+                max_len: None,
+            },
+            Constraint::RustIdent | Constraint::PythonIdent | Constraint::JsIdent => Rules {
+                cases: ALL_CASES,
+                body_seps: &["_", ""],
+                tag_seps: &["_", ""],
+                leetify: false,
+                leading_digit: false,
+                numeric_tags: false,
+                max_len: None,
+            },
+            // Unquoted identifiers fold case, so only lowercase round-trips.
+            Constraint::SqlTable => Rules {
+                cases: &[Case::Lower],
+                body_seps: &["_"],
+                tag_seps: &["_", ""],
+                leetify: false,
+                leading_digit: false,
+                numeric_tags: false,
+                max_len: Some(SQL_IDENT_MAX),
+            },
+            Constraint::EnvVar => Rules {
+                cases: &[Case::Upper],
+                body_seps: &["_"],
+                tag_seps: &["_"],
+                leetify: false,
+                leading_digit: false,
+                numeric_tags: true,
+                max_len: None,
+            },
+            Constraint::Filename => Rules {
+                cases: ALL_CASES,
+                body_seps: &["-", "_", ".", ""],
+                tag_seps: &["-", "_", ".", ""],
+                leetify: true,
+                leading_digit: true,
+                numeric_tags: false,
+                max_len: Some(FILENAME_MAX),
+            },
+            // Mail systems mostly treat local parts case-insensitively.
+            Constraint::EmailLocal => Rules {
+                cases: &[Case::Lower],
+                body_seps: &["-", "_", "."],
+                tag_seps: &["-", "_", ".", ""],
+                leetify: true,
+                leading_digit: true,
+                numeric_tags: false,
+                max_len: Some(EMAIL_LOCAL_MAX),
+            },
+        }
+    }
+
+    fn restrict<R: Rng>(&self, style: &mut Style, rng: &mut R) {
+        let rules = self.rules();
+        if !rules.cases.contains(&style.case) {
+            style.case = *rules.cases.choose(rng).unwrap();
+        }
+        // Same rule as `Style::random`: lowercase words need a visible
+        // separator or they run together.
+        let body_seps: Vec<&'static str> = rules
+            .body_seps
+            .iter()
+            .copied()
+            .filter(|sep| !(style.case == Case::Lower && sep.is_empty()))
+            .collect();
+        style.body_sep = *body_seps.choose(rng).unwrap();
+        style.tag_sep = *rules.tag_seps.choose(rng).unwrap();
+        style.leetify &= rules.leetify;
+        if !rules.leading_digit && matches!(style.tag_position, TagPosition::Prefix) {
+            style.tag_position = TagPosition::Suffix;
+        }
+        if rules.numeric_tags {
+            style.tag_kind = match style.tag_kind {
+                TagKind::Alnum(len) | TagKind::Alpha(len) | TagKind::Hex(len) => {
+                    TagKind::Digits(len)
+                }
+                numeric => numeric,
+            };
+        }
+    }
+
+    /// Trim a rendered value to the target's length limit, if it has one,
+    /// without leaving a dangling separator.
+    fn fit(&self, s: String) -> String {
+        match self.rules().max_len {
+            Some(max) if s.chars().count() > max => {
+                let mut cut: String = s.chars().take(max).collect();
+                cut.truncate(cut.trim_end_matches(['-', '_', '.']).len());
+                cut
+            }
+            _ => s,
+        }
+    }
+}
+
+const DNS_LABEL_MAX: usize = 63;
+/// PostgreSQL's limit; MySQL allows 64.
+const SQL_IDENT_MAX: usize = 63;
+const FILENAME_MAX: usize = 255;
+const EMAIL_LOCAL_MAX: usize = 64;
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen",
+    "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+    "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if",
+    "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try",
+    "while", "with", "yield",
+];
+
+const JS_RESERVED: &[&str] = &[
+    "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+    "delete", "do", "else", "enum", "export", "extends", "false", "finally", "for", "function",
+    "if", "implements", "import", "in", "instanceof", "interface", "let", "new", "null",
+    "package", "private", "protected", "public", "return", "static", "super", "switch", "this",
+    "throw", "true", "try", "typeof", "var", "void", "while", "with", "yield",
+];
+
+/// Reserved in most SQL dialects; not exhaustive for any single one.
+const SQL_RESERVED: &[&str] = &[
+    "all", "and", "any", "as", "asc", "between", "by", "case", "check", "column", "constraint",
+    "create", "default", "delete", "desc", "distinct", "drop", "else", "end", "exists",
+    "foreign", "from", "grant", "group", "having", "in", "index", "insert", "into", "is", "join",
+    "key", "like", "limit", "not", "null", "offset", "on", "or", "order", "primary",
+    "references", "select", "table", "then", "to", "union", "unique", "update", "user", "using",
+    "values", "when", "where", "with",
+];
+
+/// Device names Windows reserves regardless of extension.
+const WINDOWS_RESERVED: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// ASCII letters, digits and `_` (plus whatever `extra` allows), not
+/// starting with a digit.
+fn is_identifier(s: &str, extra: impl Fn(char) -> bool) -> bool {
+    let ok = |c: char| c.is_ascii_alphanumeric() || c == '_' || extra(c);
+    !s.is_empty()
+        && !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().all(ok)
+}
+
+/// Safe on Linux, macOS and Windows: the POSIX portable filename characters,
+/// no leading `-`/`.`, no trailing `.`, and not a Windows device name.
+fn is_portable_filename(s: &str) -> bool {
+    let stem = s.split('.').next().unwrap_or("").to_ascii_lowercase();
+    !s.is_empty()
+        && s.len() <= FILENAME_MAX
+        && !s.starts_with(['-', '.'])
+        && !s.ends_with('.')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        && !WINDOWS_RESERVED.contains(&stem.as_str())
+}
+
+/// An unquoted RFC 5322 dot-atom local part: atext characters, dots only
+/// between them, at most 64 chars.
+fn is_email_local_part(s: &str) -> bool {
+    let atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c);
+    !s.is_empty()
+        && s.len() <= EMAIL_LOCAL_MAX
+        && s.split('.').all(|atom| !atom.is_empty() && atom.chars().all(atext))
+}
+
+/// True if `s` is a DNS label per RFC 1123 that also starts with a letter
+/// (the stricter RFC 1035 form k8s names require).
+pub fn is_dns_label(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    !bytes.is_empty()
+        && bytes.len() <= DNS_LABEL_MAX
+        && bytes[0].is_ascii_lowercase()
+        && bytes[bytes.len() - 1] != b'-'
+        && bytes
+            .iter()
+            .all(|&b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// True if git would accept `name` as a branch name — the rules of
+/// `git check-ref-format --branch`, checked without shelling out.
+pub fn is_git_branch_name(name: &str) -> bool {
+    if name.is_empty() || name == "@" || name.starts_with('-') {
+        return false;
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.ends_with('.') {
+        return false;
+    }
+    if name.contains("..") || name.contains("//") || name.contains("@{") {
+        return false;
+    }
+    let forbidden = |c: char| {
+        c.is_ascii_control() || matches!(c, ' ' | '~' | '^' | ':' | '?' | '*' | '[' | '\\')
+    };
+    if name.chars().any(forbidden) {
+        return false;
+    }
+    name.split('/')
+        .all(|part| !part.starts_with('.') && !part.ends_with(".lock"))
+}
+
+// ---- Generators (public: random style each call) ------------------------
+
+pub fn uuid() -> String {
+    Uuid::new_v4().to_string()
+}
+
+pub fn uuidv7() -> String {
+    Uuid::now_v7().to_string()
+}
+
+pub fn url_safe(length: usize) -> String {
+    from_charset(length, URL_SAFE_CHARS, &mut rand::thread_rng())
+}
+
+pub fn api_key(length: usize) -> String {
+    from_charset(length, API_KEY_CHARS, &mut rand::thread_rng())
+}
+
+/// Default PIN length — matches the usual six-digit OTP code.
+pub const PIN_LENGTH: usize = 6;
+/// Shortest PIN generated; shorter lengths are raised to it. Below four
+/// digits the weak-PIN filter has next to nothing left to choose from.
+pub const PIN_MIN_LENGTH: usize = 4;
+
+/// True for PINs a guesser would try first: one repeated digit, a straight
+/// ascending/descending run, or an entry from the common-PINs list.
+pub fn is_weak_pin(pin: &str) -> bool {
+    let digits = pin.as_bytes();
+    if digits.len() < 2 {
+        return false;
+    }
+    let repeated = digits.windows(2).all(|w| w[0] == w[1]);
+    let ascending = digits.windows(2).all(|w| w[1] == w[0] + 1);
+    let descending = digits.windows(2).all(|w| w[0] == w[1] + 1);
+    repeated || ascending || descending || COMMON_PINS.contains(&pin)
+}
+
+pub fn pin(length: usize) -> String {
+    generate_with_length(RandomFormat::Pin, &Style::random(), length)
+}
+
+/// Entropy of a PIN in bits: `raw` over every `length`-digit code, and
+/// `filtered` over what's left after `pin` drops the weak ones. Lengths
+/// below [`PIN_MIN_LENGTH`] are measured at it, as they're generated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PinEntropy {
+    pub raw: f64,
+    pub filtered: f64,
+}
+
+pub fn pin_entropy(length: usize) -> PinEntropy {
+    let length = length.max(PIN_MIN_LENGTH);
+    // Every weak PIN is a repeat, a run, or a listed code, so enumerating
+    // those candidates (deduped) counts the rejected set exactly.
+    let mut weak: HashSet<String> = HashSet::new();
+    let mut candidates: Vec<String> = COMMON_PINS.iter().map(|p| p.to_string()).collect();
+    for d in 0..10u8 {
+        let start = b'0' + d;
+        candidates.push((start as char).to_string().repeat(length));
+        // A straight run can't be longer than the ten digits.
+        if length <= 10 {
+            candidates.push((0..length).map(|i| (start + i as u8) as char).collect());
+            candidates.push((0..length).map(|i| start.wrapping_sub(i as u8) as char).collect());
+        }
+    }
+    for c in candidates {
+        if c.len() == length && c.bytes().all(|b| b.is_ascii_digit()) && is_weak_pin(&c) {
+            weak.insert(c);
+        }
+    }
+
+    // In log space: 10^length overflows f64 past 308 digits.
+    let raw = length as f64 * 10f64.log2();
+    let kept = -(weak.len() as f64 / 10f64.powi(length as i32));
+    PinEntropy {
+        raw,
+        filtered: raw + kept.ln_1p() / std::f64::consts::LN_2,
+    }
+}
+
+/// Default `bytes` length — 256 bits, like `openssl rand -hex 32`.
+pub const BYTES_LENGTH: usize = 32;
+
+/// `bytes` random bytes, encoded. Entropy is exactly `8 × bytes`.
+pub fn random_encoded(bytes: usize, encoding: ByteEncoding) -> String {
+    encode_bytes(&random_bytes(bytes, &mut rand::thread_rng()), encoding)
+}
+
+/// Default TOTP secret size in bytes — 160 bits, as RFC 4226 recommends.
+pub const TOTP_SECRET_BYTES: usize = 20;
+/// The shortest secret RFC 4226 allows: 128 bits.
+pub const TOTP_SECRET_MIN_BYTES: usize = 16;
+/// TOTP time step in seconds. Authenticator apps assume 30.
+pub const TOTP_PERIOD: u64 = 30;
+
+/// Base32 TOTP secret drawn from `bytes` random bytes.
+pub fn totp_secret(bytes: usize) -> String {
+    base32_encode(&random_bytes(bytes, &mut rand::thread_rng()))
+}
+
+/// `otpauth://totp/` provisioning URI (what 2FA QR codes carry) for a
+/// base32 `secret`, using SHA1 and a 30s period. An empty or non-base32
+/// secret would give every device the same useless key, so it's refused.
+pub fn otpauth_uri(
+    issuer: &str,
+    account: &str,
+    secret: &str,
+    digits: u32,
+) -> Result<String, SecretError> {
+    match base32_decode(secret) {
+        None => return Err(SecretError::NotBase32),
+        Some(key) if key.is_empty() => return Err(SecretError::Empty),
+        Some(_) => {}
+    }
+    Ok(format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(issuer),
+        digits,
+        TOTP_PERIOD
+    ))
+}
+
+/// Why a TOTP secret can't be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretError {
+    NotBase32,
+    Empty,
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretError::NotBase32 => write!(f, "the secret isn't valid base32"),
+            SecretError::Empty => write!(f, "the secret is empty"),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+/// Code lengths `totp_code` makes: RFC 4226 asks for at least 6 digits,
+/// and authenticator apps stop at 8.
+pub const TOTP_DIGITS: RangeInclusive<u32> = 6..=8;
+
+/// `totp_code` was asked for a length outside `TOTP_DIGITS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TotpDigitsError(pub u32);
+
+impl fmt::Display for TotpDigitsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TOTP codes have {} to {} digits, not {}",
+            TOTP_DIGITS.start(),
+            TOTP_DIGITS.end(),
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for TotpDigitsError {}
+
+/// RFC 6238 TOTP code (HMAC-SHA1) for the raw `secret` at `unix_time`.
+pub fn totp_code(secret: &[u8], unix_time: u64, digits: u32) -> Result<String, TotpDigitsError> {
+    if !TOTP_DIGITS.contains(&digits) {
+        return Err(TotpDigitsError(digits));
+    }
+    let counter = unix_time / TOTP_PERIOD;
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation (RFC 4226 §5.3).
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    let code = binary as u64 % 10u64.pow(digits);
+    Ok(format!("{:0w$}", code, w = digits as usize))
+}
+
+/// Replace `words` with a fresh pick for a word format. Leaves it empty
+/// for the rest.
+fn pick_words<R: Rng + ?Sized>(format: RandomFormat, rng: &mut R, words: &mut Vec<&'static str>) {
+    words.clear();
+    match format {
+        RandomFormat::MemorableName => {
+            words.extend([random_item(ADJECTIVES, rng), random_item(NOUNS, rng)])
+        }
+        RandomFormat::HistoricalFigure => words.push(random_item(FIGURES, rng)),
+        RandomFormat::GeographicName => words.push(random_item(LOCATIONS, rng)),
+        RandomFormat::CharacterName => words.push(random_item(CHARACTERS, rng)),
+        RandomFormat::PhoneticAlphabet => {
+            let count = rng.gen_range(2..=3);
+            words.extend((0..count).map(|_| random_item(PHONETICS, rng)))
+        }
+        RandomFormat::RhymingPair => {
+            let (a, b) = random_item(RHYME_PAIRS, rng);
+            words.extend([a, b])
+        }
+        RandomFormat::MusicalTerm => {
+            let count = rng.gen_range(1..=2);
+            words.extend((0..count).map(|_| random_item(MUSICAL, rng)))
+        }
+        RandomFormat::ScientificElement => {
+            let count = rng.gen_range(1..=2);
+            words.extend((0..count).map(|_| random_item(ELEMENTS, rng)))
+        }
+        RandomFormat::ConstellationName => words.push(random_item(CONSTELLATIONS, rng)),
+        RandomFormat::SportsReference => words.push(random_item(SPORTS, rng)),
+        RandomFormat::FoodCombination => {
+            words.extend([random_item(FOOD_ADJECTIVES, rng), random_item(FOODS, rng)])
+        }
+        // Non-wordlist formats: caller shouldn't reach here.
+        _ => {}
+    }
+}
+
+/// Default syllable count for `word` — four syllables, `ta-vo-re-ni`.
+pub const SYLLABLES: usize = 4;
+
+/// At least one syllable: an empty word would leave only the tag, which
+/// can't start an identifier or DNS label.
+fn push_pronounceable<R: Rng + ?Sized>(word: &mut String, syllables: usize, rng: &mut R) {
+    for _ in 0..syllables.max(1) {
+        word.push(*ONSETS.choose(rng).unwrap());
+        word.push(*VOWELS.choose(rng).unwrap());
+        if rng.gen_bool(CLOSED_SYLLABLE_P) {
+            word.push(*FINALS.choose(rng).unwrap());
+        }
+    }
+}
+
+/// Bits of entropy in a `syllables`-long `word` before styling: the
+/// open/closed choice plus the letters, per syllable.
+pub fn pronounceable_entropy(syllables: usize) -> f64 {
+    let p = CLOSED_SYLLABLE_P;
+    let open = (ONSETS.len() * VOWELS.len()) as f64;
+    let closed = open * FINALS.len() as f64;
+    let template = -(p * p.log2() + (1.0 - p) * (1.0 - p).log2());
+    let per_syllable = template + (1.0 - p) * open.log2() + p * closed.log2();
+    syllables as f64 * per_syllable
+}
+
+pub fn pronounceable(syllables: usize) -> String {
+    generate_with_length(RandomFormat::Pronounceable, &Style::random(), syllables)
+}
+
+pub fn proquint() -> String {
+    proquint_encode(rand::thread_rng().gen())
+}
+
+pub fn memorable_name() -> String {
+    generate_with_style(RandomFormat::MemorableName, &Style::random())
+}
+pub fn historical_figure() -> String {
+    generate_with_style(RandomFormat::HistoricalFigure, &Style::random())
+}
+pub fn geographic_name() -> String {
+    generate_with_style(RandomFormat::GeographicName, &Style::random())
+}
+pub fn character_name() -> String {
+    generate_with_style(RandomFormat::CharacterName, &Style::random())
+}
+pub fn phonetic_alphabet() -> String {
+    generate_with_style(RandomFormat::PhoneticAlphabet, &Style::random())
+}
+pub fn rhyming_pair() -> String {
+    generate_with_style(RandomFormat::RhymingPair, &Style::random())
+}
+pub fn musical_term() -> String {
+    generate_with_style(RandomFormat::MusicalTerm, &Style::random())
+}
+pub fn scientific_element() -> String {
+    generate_with_style(RandomFormat::ScientificElement, &Style::random())
+}
+pub fn constellation_name() -> String {
+    generate_with_style(RandomFormat::ConstellationName, &Style::random())
+}
+pub fn sports_reference() -> String {
+    generate_with_style(RandomFormat::SportsReference, &Style::random())
+}
+pub fn food_combination() -> String {
+    generate_with_style(RandomFormat::FoodCombination, &Style::random())
+}
+
+// ---- Top-level entry points --------------------------------------------
+
+pub fn generate(format: RandomFormat) -> String {
+    generate_with_style(format, &Style::random())
+}
+
+/// Generate using a caller-provided style — use this when producing a batch
+/// of items that should share visual shape (one section of the menu, or a
+/// `randr <format>` listing).
+pub fn generate_with_style(format: RandomFormat, style: &Style) -> String {
+    generate_with_length(format, style, format.default_length().unwrap_or(0))
+}
+
+/// Like `generate_with_style`, but with an explicit length for the sized
+/// formats (`url`, `api`, `pin`, `totp`/`bytes` in bytes, `word` in
+/// syllables). Every other format ignores `length`.
+pub fn generate_with_length(format: RandomFormat, style: &Style, length: usize) -> String {
+    generate_styled(format, style, length).text
+}
+
+/// `generate_with_length`, keeping which parts of the value are words,
+/// tags and separators.
+pub fn generate_styled(format: RandomFormat, style: &Style, length: usize) -> Styled {
+    let mut generator = Generator::with_rng(format, style.clone(), length, rand::thread_rng());
+    generator.next_styled();
+    generator.value
+}
+
+/// Values of one format in one style from a single RNG, reusing its buffers
+/// from one value to the next — the way to make many. `next_str` allocates
+/// nothing once the buffers have grown to size.
+///
+/// `Generator::new` seeds a `StdRng` (ChaCha12, a CSPRNG) from the thread
+/// RNG; `with_rng` takes any other.
+#[derive(Debug)]
+pub struct Generator<R = StdRng> {
+    format: RandomFormat,
+    style: Style,
+    length: usize,
+    encoding: ByteEncoding,
+    rng: R,
+    v7: Option<V7Block>,
+    value: Styled,
+    words: Vec<&'static str>,
+    word: String,
+    bytes: Vec<u8>,
+    scratch: Vec<u8>,
+}
+
+impl Generator {
+    pub fn new(format: RandomFormat, style: Style, length: usize) -> Self {
+        let rng = StdRng::from_rng(rand::thread_rng()).expect("the thread RNG never fails");
+        Generator::with_rng(format, style, length, rng)
+    }
+}
+
+impl<R: Rng> Generator<R> {
+    pub fn with_rng(format: RandomFormat, style: Style, length: usize, rng: R) -> Self {
+        Generator {
+            format,
+            style,
+            length,
+            encoding: ByteEncoding::default(),
+            rng,
+            v7: None,
+            value: Styled::default(),
+            words: Vec::new(),
+            word: String::new(),
+            bytes: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Encode `bytes` values with `encoding` instead of hex. The other
+    /// formats ignore it.
+    pub fn with_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// The next value. It lives in a buffer the following call overwrites;
+    /// clone it to keep it.
+    pub fn next_styled(&mut self) -> &Styled {
+        let Generator {
+            format,
+            style,
+            length,
+            encoding,
+            rng,
+            v7,
+            value: out,
+            words,
+            word,
+            bytes,
+            scratch,
+        } = self;
+        let length = *length;
+        out.clear();
+        out.push(Part::Plain, &style.prefix);
+        match format {
+            RandomFormat::Uuid => {
+                let id = uuid::Builder::from_random_bytes(rng.gen()).into_uuid();
+                out.push(Part::Plain, id.hyphenated().encode_lower(&mut Uuid::encode_buffer()));
+            }
+            RandomFormat::UuidV7 => {
+                let id = match v7 {
+                    Some(block) => block.next(rng),
+                    None => Uuid::now_v7(),
+                };
+                out.push(Part::Plain, id.hyphenated().encode_lower(&mut Uuid::encode_buffer()));
+            }
+            RandomFormat::UrlSafe => {
+                out.push_with(Part::Plain, |text| push_charset(text, length, URL_SAFE_CHARS, rng))
+            }
+            RandomFormat::ApiKey => {
+                out.push_with(Part::Plain, |text| push_charset(text, length, API_KEY_CHARS, rng))
+            }
+            RandomFormat::Pin => loop {
+                out.clear();
+                out.push(Part::Plain, &style.prefix);
+                let digits = length.max(PIN_MIN_LENGTH);
+                out.push_with(Part::Plain, |text| push_charset(text, digits, DIGITS, rng));
+                if !is_weak_pin(&out.text[style.prefix.len()..]) {
+                    break;
+                }
+            },
+            RandomFormat::TotpSecret => {
+                fill_random_bytes(bytes, length, rng);
+                out.push_with(Part::Plain, |text| push_base32(text, bytes))
+            }
+            RandomFormat::Bytes => {
+                fill_random_bytes(bytes, length, rng);
+                out.push_with(Part::Plain, |text| push_encoded(text, bytes, *encoding, scratch))
+            }
+            RandomFormat::Pronounceable => {
+                word.clear();
+                push_pronounceable(word, length, rng);
+                render_into(&[word.as_str()], style, rng, out).expect(BUILT_IN_WORDS_FIT);
+            }
+            RandomFormat::Proquint => {
+                out.push_with(Part::Plain, |text| push_proquint(text, rng.gen()))
+            }
+            _ => {
+                pick_words(*format, rng, words);
+                render_into(words, style, rng, out).expect(BUILT_IN_WORDS_FIT);
+            }
+        }
+        &self.value
+    }
+
+    /// `next_styled`'s text.
+    pub fn next_str(&mut self) -> &str {
+        &self.next_styled().text
+    }
+
+    /// Count `uuidv7` values up from `block` instead of the shared clock.
+    /// Only the block's reserved run keeps sort order with other blocks.
+    pub fn use_v7_block(&mut self, block: V7Block) {
+        self.v7 = Some(block);
+    }
+}
+
+/// Bits of the UUIDv7 counter: the 12 of `rand_a` plus the top 30 of
+/// `rand_b`, as the `uuid` crate lays it out. The other 32 stay random.
+const V7_COUNTER_BITS: u32 = 42;
+
+/// Hands out runs of UUIDv7 timestamp + counter in order, so runs filled on
+/// different threads still sort in the order they were reserved.
+#[derive(Debug, Default)]
+pub struct V7Clock {
+    millis: u64,
+    counter: u64,
+}
+
+/// A reserved run of UUIDv7s: one millisecond timestamp, consecutive
+/// counter values.
+#[derive(Debug, Clone, Copy)]
+pub struct V7Block {
+    millis: u64,
+    counter: u64,
+}
+
+impl V7Clock {
+    pub fn new() -> Self {
+        V7Clock::default()
+    }
+
+    /// Reserve the next `n` values. Like `Uuid::now_v7`, a new millisecond
+    /// restarts the counter at a random point in its lower half, and a full
+    /// counter borrows the next millisecond.
+    pub fn reserve(&mut self, n: u64) -> V7Block {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        if now > self.millis {
+            self.millis = now;
+            self.counter = rand::thread_rng().gen_range(0..1 << (V7_COUNTER_BITS - 1));
+        }
+        if self.counter + n > 1 << V7_COUNTER_BITS {
+            self.millis += 1;
+            self.counter = 0;
+        }
+        let block = V7Block {
+            millis: self.millis,
+            counter: self.counter,
+        };
+        self.counter += n;
+        block
+    }
+}
+
+impl V7Block {
+    fn next<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Uuid {
+        let counter = self.counter << (64 - V7_COUNTER_BITS);
+        self.counter += 1;
+        let mut bytes = [0u8; 10];
+        // The builder takes rand_a from the low 12 bits of bytes 0-1 and
+        // rand_b from the low 6 bits of byte 2 onwards.
+        bytes[0] = (counter >> 60) as u8;
+        bytes[1] = (counter >> 52) as u8;
+        bytes[2..6].copy_from_slice(&((counter << 12) >> 2).to_be_bytes()[..4]);
+        bytes[6..].copy_from_slice(&rng.gen::<[u8; 4]>());
+        uuid::Builder::from_unix_timestamp_millis(self.millis, &bytes).into_uuid()
+    }
+}
+
+// ---- Streaming ----------------------------------------------------------
+
+/// Endless values of one format in one style, each generated as it's pulled,
+/// so `take(n)` of millions never holds more than one at a time. Use a
+/// `Generator` directly to skip the `String` per value.
+#[derive(Debug)]
+pub struct Stream {
+    generator: Generator,
+}
+
+/// A `Stream` of `format` values in `style`, `length` as for
+/// `generate_with_length`.
+pub fn stream(format: RandomFormat, style: Style, length: usize) -> Stream {
+    Stream {
+        generator: Generator::new(format, style, length),
+    }
+}
+
+impl Iterator for Stream {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        Some(self.generator.next_str().to_string())
+    }
+}
+
+// ---- Output space and uniqueness ---------------------------------------
+
+/// One shape a format's words can come out in: how many words, how likely
+/// this shape is, how many distinct combinations it has, the chance two
+/// draws of it match (Σp²), and its mean length in characters.
+#[derive(Debug, Clone, Copy)]
+struct WordGroup {
+    words: usize,
+    weight: f64,
+    combos: f64,
+    sum_sq: f64,
+    mean_len: f64,
+}
+
+impl WordGroup {
+    /// One word drawn uniformly from `list` (duplicates make some words
+    /// likelier, which Σp² accounts for).
+    fn list(list: &[&str]) -> WordGroup {
+        let mut counts: std::collections::HashMap<&str, usize> = Default::default();
+        for w in list {
+            *counts.entry(w).or_insert(0) += 1;
+        }
+        let n = list.len() as f64;
+        WordGroup {
+            words: 1,
+            weight: 1.0,
+            combos: counts.len() as f64,
+            sum_sq: counts.values().map(|&c| (c as f64 / n).powi(2)).sum(),
+            mean_len: list.iter().map(|w| w.len()).sum::<usize>() as f64 / n,
+        }
+    }
+
+    /// Independent groups drawn one after another.
+    fn then(self, next: WordGroup) -> WordGroup {
+        WordGroup {
+            words: self.words + next.words,
+            weight: self.weight * next.weight,
+            combos: self.combos * next.combos,
+            sum_sq: self.sum_sq * next.sum_sq,
+            mean_len: self.mean_len + next.mean_len,
+        }
+    }
+
+    fn repeat(self, times: usize) -> WordGroup {
+        (1..times).fold(self, |acc, _| acc.then(self))
+    }
+
+    fn weighted(mut self, weight: f64) -> WordGroup {
+        self.weight = weight;
+        self
+    }
+}
+
+fn word_groups(format: RandomFormat, length: usize) -> Vec<WordGroup> {
+    match format {
+        RandomFormat::MemorableName => {
+            vec![WordGroup::list(ADJECTIVES).then(WordGroup::list(NOUNS))]
+        }
+        RandomFormat::HistoricalFigure => vec![WordGroup::list(FIGURES)],
+        RandomFormat::GeographicName => vec![WordGroup::list(LOCATIONS)],
+        RandomFormat::CharacterName => vec![WordGroup::list(CHARACTERS)],
+        RandomFormat::PhoneticAlphabet => {
+            let p = WordGroup::list(PHONETICS);
+            vec![p.repeat(2).weighted(0.5), p.repeat(3).weighted(0.5)]
+        }
+        RandomFormat::RhymingPair => {
+            let n = RHYME_PAIRS.len() as f64;
+            let chars: usize = RHYME_PAIRS.iter().map(|(a, b)| a.len() + b.len()).sum();
+            vec![WordGroup {
+                words: 2,
+                weight: 1.0,
+                combos: n,
+                sum_sq: 1.0 / n,
+                mean_len: chars as f64 / n,
+            }]
+        }
+        RandomFormat::MusicalTerm => {
+            let m = WordGroup::list(MUSICAL);
+            vec![m.weighted(0.5), m.repeat(2).weighted(0.5)]
+        }
+        RandomFormat::ScientificElement => {
+            let e = WordGroup::list(ELEMENTS);
+            vec![e.weighted(0.5), e.repeat(2).weighted(0.5)]
+        }
+        RandomFormat::ConstellationName => vec![WordGroup::list(CONSTELLATIONS)],
+        RandomFormat::SportsReference => vec![WordGroup::list(SPORTS)],
+        RandomFormat::FoodCombination => {
+            vec![WordGroup::list(FOOD_ADJECTIVES).then(WordGroup::list(FOODS))]
+        }
+        RandomFormat::Pronounceable => {
+            let p = CLOSED_SYLLABLE_P;
+            let open = (ONSETS.len() * VOWELS.len()) as f64;
+            let closed = open * FINALS.len() as f64;
+            let syllable = WordGroup {
+                words: 1,
+                weight: 1.0,
+                combos: open + closed,
+                sum_sq: (1.0 - p).powi(2) / open + p.powi(2) / closed,
+                mean_len: 2.0 + p,
+            };
+            let length = length.max(1);
+            vec![WordGroup {
+                words: 1,
+                weight: 1.0,
+                combos: syllable.combos.powi(length as i32),
+                sum_sq: syllable.sum_sq.powi(length as i32),
+                mean_len: syllable.mean_len * length as f64,
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+/// Distinct tags, Σp² over them, and mean length for a tag shape.
+fn tag_stats(tag_kind: TagKind) -> (f64, f64, f64) {
+    let uniform = |alphabet: f64, len: usize| {
+        let n = alphabet.powi(len as i32);
+        (n, 1.0 / n, len as f64)
+    };
+    match tag_kind {
+        // 1..=max with max one of 9/99/999/9999, so small numbers are much
+        // likelier than large ones.
+        TagKind::SmallInt => {
+            let maxes = [9u32, 99, 999, 9999];
+            let p = |v: u32| {
+                maxes.iter().filter(|&&m| m >= v).map(|&m| 1.0 / m as f64).sum::<f64>() / 4.0
+            };
+            let digits = |v: u32| v.to_string().len() as f64;
+            let sum_sq = (1..=9999).map(|v| p(v).powi(2)).sum();
+            let mean_len = (1..=9999).map(|v| p(v) * digits(v)).sum();
+            (9999.0, sum_sq, mean_len)
+        }
+        TagKind::Alnum(len) => uniform(36.0, len),
+        TagKind::Alpha(len) => uniform(26.0, len),
+        TagKind::Hex(len) => uniform(16.0, len),
+        TagKind::Digits(len) => uniform(10.0, len),
+    }
+}
+
+fn tag_positions(style: &Style, words: usize) -> f64 {
+    match style.tag_position {
+        TagPosition::Middle if words > 1 => (words - 1) as f64,
+        _ => 1.0,
+    }
+}
+
+/// How many distinct values `format` can produce under `style` (and
+/// `length`, for sized formats). Leet variants aren't counted — to a reader
+/// `b0ld-fox` and `bold-fox` are the same name — so for leet styles this is
+/// the space of names, not of exact strings.
+pub fn output_space(format: RandomFormat, style: &Style, length: usize) -> f64 {
+    match format {
+        RandomFormat::Uuid | RandomFormat::UuidV7 => 2f64.powi(122),
+        RandomFormat::UrlSafe => 64f64.powi(length as i32),
+        RandomFormat::ApiKey => 36f64.powi(length as i32),
+        RandomFormat::Pin => 2f64.powf(pin_entropy(length).filtered),
+        RandomFormat::TotpSecret | RandomFormat::Bytes => 256f64.powi(length as i32),
+        RandomFormat::Proquint => 2f64.powi(32),
+        _ => {
+            let (tags, _, _) = tag_stats(style.tag_kind);
+            word_groups(format, length)
+                .into_iter()
+                .map(|g| g.combos * tag_positions(style, g.words) * tags)
+                .sum()
+        }
+    }
+}
+
+/// Why `generate_unique` couldn't produce the requested count.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniqueError {
+    /// More values were asked for than the format/style can produce at
+    /// all, before counting values already issued.
+    SpaceTooSmall { requested: usize, space: u64 },
+    /// Gave up after too many repeats in a row — the reachable space is
+    /// smaller than `output_space` estimated (e.g. truncation by a target),
+    /// or earlier runs already issued most of it.
+    Exhausted { requested: usize, found: usize },
+}
+
+impl fmt::Display for UniqueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UniqueError::SpaceTooSmall { requested, space } => write!(
+                f,
+                "asked for {} unique values but this format/style only has {}",
+                requested, space
+            ),
+            UniqueError::Exhausted { requested, found } => write!(
+                f,
+                "only found {} of {} unique values before running dry",
+                found, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UniqueError {}
+
+/// Consecutive duplicates `generate_unique` tolerates before giving up.
+/// Collecting all of a 200-value space needs ~200 misses in a row at worst
+/// in practice, so this only trips when the space is really used up.
+const MAX_CONSECUTIVE_REPEATS: usize = 10_000;
+
+/// `n` distinct values sharing `style`, in generation order.
+pub fn generate_unique(
+    format: RandomFormat,
+    style: &Style,
+    n: usize,
+) -> Result<Vec<String>, UniqueError> {
+    generate_unique_with_length(format, style, n, format.default_length().unwrap_or(0))
+}
+
+/// `generate_unique` with an explicit length for sized formats.
+pub fn generate_unique_with_length(
+    format: RandomFormat,
+    style: &Style,
+    n: usize,
+    length: usize,
+) -> Result<Vec<String>, UniqueError> {
+    generate_unique_excluding(format, style, n, length, &HashSet::new())
+}
+
+/// `generate_unique_with_length` that also skips everything in `issued` —
+/// values handed out earlier, e.g. by a previous run via the ledger.
+pub fn generate_unique_excluding(
+    format: RandomFormat,
+    style: &Style,
+    n: usize,
+    length: usize,
+    issued: &HashSet<String>,
+) -> Result<Vec<String>, UniqueError> {
+    let items = generate_unique_styled(format, style, n, length, issued)?;
+    Ok(items.into_iter().map(|item| item.text).collect())
+}
+
+/// `generate_unique_excluding`, returning `generate_styled` values.
+pub fn generate_unique_styled(
+    format: RandomFormat,
+    style: &Style,
+    n: usize,
+    length: usize,
+    issued: &HashSet<String>,
+) -> Result<Vec<Styled>, UniqueError> {
+    Generator::with_rng(format, style.clone(), length, rand::thread_rng()).next_unique(n, issued)
+}
+
+impl<R: Rng> Generator<R> {
+    /// The next `n` values, distinct from each other and from everything in
+    /// `issued`.
+    pub fn next_unique(
+        &mut self,
+        n: usize,
+        issued: &HashSet<String>,
+    ) -> Result<Vec<Styled>, UniqueError> {
+        let (format, length) = (self.format, self.length);
+        // Rounded: the PIN space goes through log2 and back. `issued` isn't
+        // subtracted: a ledger namespace holds every format's values, most
+        // of which this one could never produce. When the ones it could
+        // have used the space up, the repeat cap below catches it.
+        let space = output_space(format, &self.style, length).round();
+        if (n as f64) > space {
+            return Err(UniqueError::SpaceTooSmall {
+                requested: n,
+                space: space as u64,
+            });
+        }
+
+        let mut seen = HashSet::with_capacity(n);
+        let mut items = Vec::with_capacity(n);
+        let mut repeats = 0;
+        while items.len() < n {
+            let item = self.next_styled();
+            if !issued.contains(&item.text) && seen.insert(item.text.clone()) {
+                items.push(item.clone());
+                repeats = 0;
+            } else {
+                repeats += 1;
+                if repeats > MAX_CONSECUTIVE_REPEATS {
+                    return Err(UniqueError::Exhausted {
+                        requested: n,
+                        found: items.len(),
+                    });
+                }
+            }
+        }
+        Ok(items)
+    }
+}
+
+// ---- Collisions ---------------------------------------------------------
+
+/// The "effective" space for birthday collisions: `1 / Σp²` over every
+/// output. Equals `output_space` for uniform formats and is smaller when
+/// some outputs are likelier (small-int tags, duplicated wordlist entries,
+/// mixed word counts). Leet is ignored, as in `output_space`.
+pub fn collision_space(format: RandomFormat, style: &Style, length: usize) -> f64 {
+    let groups = word_groups(format, length);
+    if groups.is_empty() {
+        return output_space(format, style, length);
+    }
+    let (_, tag_sum_sq, _) = tag_stats(style.tag_kind);
+    let sum_sq: f64 = groups
+        .iter()
+        .map(|g| g.weight.powi(2) * g.sum_sq * tag_sum_sq / tag_positions(style, g.words))
+        .sum();
+    1.0 / sum_sq
+}
+
+/// Collision entropy in bits — `log2(collision_space)`.
+pub fn collision_bits(format: RandomFormat, style: &Style, length: usize) -> f64 {
+    collision_space(format, style, length).log2()
+}
+
+/// Birthday-bound chance that `count` draws from a space of effective size
+/// `space` contain at least one repeat: `1 - e^(-n(n-1) / 2·space)`.
+pub fn collision_probability(space: f64, count: u64) -> f64 {
+    if count <= 1 {
+        return 0.0;
+    }
+    let n = count as f64;
+    let exponent = n * (n - 1.0) / (2.0 * space);
+    -(-exponent).exp_m1()
+}
+
+/// Mean output length in characters for `format` under `style`.
+pub fn mean_chars(format: RandomFormat, style: &Style, length: usize) -> f64 {
+    let groups = word_groups(format, length);
+    if groups.is_empty() {
+        return match format {
+            RandomFormat::Uuid | RandomFormat::UuidV7 => 36.0,
+            RandomFormat::Proquint => 11.0,
+            // Hex, as `Candidate`s are measured.
+            RandomFormat::Bytes => (length * 2) as f64,
+            RandomFormat::TotpSecret => (length * 8).div_ceil(5) as f64,
+            RandomFormat::Pin => length.max(PIN_MIN_LENGTH) as f64,
+            _ => length as f64,
+        };
+    }
+    let (_, _, tag_len) = tag_stats(style.tag_kind);
+    groups
+        .iter()
+        .map(|g| {
+            let tag_seps = if tag_positions(style, g.words) > 1.0 { 2 } else { 1 };
+            let seps = (g.words - 1) * style.body_sep.len() + tag_seps * style.tag_sep.len();
+            g.weight * (g.mean_len + seps as f64 + tag_len)
+        })
+        .sum()
+}
+
+/// A format/tag/length combination `cheapest` can recommend.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub format: RandomFormat,
+    /// Tag shape, for styled formats.
+    pub tag: Option<TagKind>,
+    pub length: usize,
+    pub bits: f64,
+    pub mean_chars: f64,
+}
+
+impl Candidate {
+    fn new(format: RandomFormat, tag: Option<TagKind>, length: usize) -> Candidate {
+        let style = Style::plain(tag.unwrap_or(TagKind::SmallInt));
+        Candidate {
+            format,
+            tag,
+            length,
+            bits: collision_bits(format, &style, length),
+            mean_chars: mean_chars(format, &style, length),
+        }
+    }
+
+    pub fn probability(&self, count: u64) -> f64 {
+        collision_probability(2f64.powf(self.bits), count)
+    }
+}
+
+/// Every tag shape for a styled format (lowercase, `-` separated, tag as
+/// suffix), or just the format itself at `length` for the rest.
+pub fn candidates(format: RandomFormat, length: usize) -> Vec<Candidate> {
+    if word_groups(format, length).is_empty() {
+        return vec![Candidate::new(format, None, length)];
+    }
+    TagKind::all()
+        .into_iter()
+        .map(|tag| Candidate::new(format, Some(tag), length))
+        .collect()
+}
+
+/// The shortest option (by mean characters) across every format whose
+/// collision chance over `count` values stays at or below
+/// `max_probability`. Sized formats are tried at each length up to 64.
+/// Word formats only vary the tag shape: every candidate is measured in
+/// the plain style (lowercase, `-` separators, tag as suffix).
+pub fn cheapest(count: u64, max_probability: f64) -> Option<Candidate> {
+    let mut options = Vec::new();
+    for format in RandomFormat::all() {
+        match format {
+            // Same space per character as `url`, just longer.
+            RandomFormat::Bytes | RandomFormat::TotpSecret => {}
+            RandomFormat::Pin => {
+                options.extend((PIN_MIN_LENGTH..=64).flat_map(|len| candidates(format, len)));
+            }
+            RandomFormat::UrlSafe | RandomFormat::ApiKey => {
+                options.extend((1..=64).flat_map(|len| candidates(format, len)));
+            }
+            _ => options.extend(candidates(format, format.default_length().unwrap_or(0))),
+        }
+    }
+    options
+        .into_iter()
+        .filter(|c| c.probability(count) <= max_probability)
+        .min_by(|a, b| a.mean_chars.total_cmp(&b.mean_chars))
+}
+
+// ---- Clipboard ----------------------------------------------------------
+
+/// OSC 52 "set clipboard" escape sequence for `text`. The terminal emulator
+/// does the copying, so it works over SSH with no clipboard libraries.
+///
+/// Inside tmux (`tmux` = true) the sequence is wrapped in a DCS passthrough
+/// so it reaches the outer terminal even when tmux's own `set-clipboard` is
+/// off; escapes inside the passthrough are doubled, as tmux requires.
+pub fn osc52(text: &str, tmux: bool) -> String {
+    let seq = format!(
+        "\x1b]52;c;{}\x07",
+        encode_bytes(text.as_bytes(), ByteEncoding::Base64)
+    );
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else {
+        seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_wordlist(format: RandomFormat) -> bool {
+        let mut words = Vec::new();
+        pick_words(format, &mut rand::thread_rng(), &mut words);
+        !words.is_empty()
+    }
+
+    #[test]
+    fn test_uuid() {
+        let s = uuid();
+        assert_eq!(s.len(), 36);
+        assert!(s.chars().all(|c| c.is_ascii_hexdigit() || c == '-'));
+    }
+
+    #[test]
+    fn test_uuidv7() {
+        let s = uuidv7();
+        assert_eq!(s.len(), 36);
         assert!(s.chars().all(|c| c.is_ascii_hexdigit() || c == '-'));
     }
 
@@ -522,4 +2583,683 @@ mod tests {
         assert_eq!(s.len(), 10);
         assert!(s.chars().all(|c| c.is_uppercase() || c.is_numeric()));
     }
+
+    #[test]
+    fn test_pin() {
+        for _ in 0..200 {
+            let s = pin(4);
+            assert_eq!(s.len(), 4);
+            assert!(s.chars().all(|c| c.is_ascii_digit()));
+            assert!(!is_weak_pin(&s));
+        }
+        // Too short to filter: raised to the minimum instead.
+        for length in [0, 1, 3] {
+            assert_eq!(pin(length).len(), PIN_MIN_LENGTH);
+            assert_eq!(pin_entropy(length), pin_entropy(PIN_MIN_LENGTH));
+        }
+    }
+
+    #[test]
+    fn test_is_weak_pin() {
+        for weak in ["0000", "999999", "1234", "456789", "9876", "3210", "1212", "123123"] {
+            assert!(is_weak_pin(weak), "{weak} should be weak");
+        }
+        for ok in ["1235", "9071", "482913", "13"] {
+            assert!(!is_weak_pin(ok), "{ok} should pass");
+        }
+    }
+
+    #[test]
+    fn test_pin_entropy() {
+        // 4 digits: 10 repeats + 7 ascending + 7 descending runs, plus the
+        // 4-digit common PINs that aren't already one of those.
+        let common4 = COMMON_PINS.iter().filter(|p| p.len() == 4).count();
+        let e = pin_entropy(4);
+        assert!((e.raw - 10_000f64.log2()).abs() < 1e-9);
+        let expected = (10_000 - 24 - common4) as f64;
+        assert!((e.filtered - expected.log2()).abs() < 1e-9);
+
+        // Far past the longest possible run, and past f64's range.
+        for length in [11, 200, 250, 400] {
+            let e = pin_entropy(length);
+            assert!((e.raw - length as f64 * 10f64.log2()).abs() < 1e-6, "{length}");
+            assert!(e.filtered.is_finite() && e.filtered <= e.raw, "{length}");
+        }
+        let style = Style::random();
+        let pins = generate_unique_with_length(RandomFormat::Pin, &style, 2, 250).unwrap();
+        assert!(pins.iter().all(|p| p.len() == 250 && !is_weak_pin(p)));
+    }
+
+    #[test]
+    fn test_base32() {
+        // RFC 4648 §10 vectors, minus the padding.
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+        for (raw, encoded) in vectors {
+            assert_eq!(base32_encode(raw.as_bytes()), encoded);
+            assert_eq!(base32_decode(encoded).unwrap(), raw.as_bytes());
+        }
+        assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_none());
+        // `Ł` isn't ASCII, so it mustn't truncate to a valid letter.
+        assert!(base32_decode("ŁŁŁŁŁŁŁŁ").is_none());
+        // "MY" is "f"; "MZ" sets a padding bit.
+        assert!(base32_decode("MZ").is_none());
+        assert!(base32_decode("MZXW6YTBOJ").is_none());
+        // Leftovers of 1, 3 or 6 characters aren't whole bytes.
+        for bad in ["A", "MZX", "MZXW6YTBA", "MZXW6YTBOIA", "MZXW6YTBOIAAAA"] {
+            assert!(base32_decode(bad).is_none(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_totp_secret() {
+        let s = totp_secret(TOTP_SECRET_BYTES);
+        assert_eq!(s.len(), 32);
+        assert_eq!(base32_decode(&s).unwrap().len(), TOTP_SECRET_BYTES);
+    }
+
+    #[test]
+    fn test_totp_code() {
+        // RFC 6238 appendix B, SHA1 column.
+        let secret = b"12345678901234567890";
+        let vectors = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+        for (time, code) in vectors {
+            assert_eq!(totp_code(secret, time, 8).unwrap(), code);
+        }
+        assert_eq!(totp_code(secret, 59, 6).unwrap(), "287082");
+        assert_eq!(totp_code(secret, 59, 0), Err(TotpDigitsError(0)));
+        assert_eq!(totp_code(secret, 59, 20), Err(TotpDigitsError(20)));
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        assert_eq!(
+            otpauth_uri("ACME Co", "alice@example.com", "JBSWY3DPEHPK3PXP", 6).unwrap(),
+            "otpauth://totp/ACME%20Co:alice%40example.com?secret=JBSWY3DPEHPK3PXP\
+             &issuer=ACME%20Co&algorithm=SHA1&digits=6&period=30"
+        );
+        assert_eq!(otpauth_uri("x", "y", "", 6), Err(SecretError::Empty));
+        assert_eq!(otpauth_uri("x", "y", "A", 6), Err(SecretError::NotBase32));
+    }
+
+    #[test]
+    fn test_encode_bytes() {
+        // RFC 4648 §10 base64 vectors.
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (raw, encoded) in vectors {
+            assert_eq!(encode_bytes(raw.as_bytes(), ByteEncoding::Base64), encoded);
+        }
+
+        let data = [0xfb, 0xff, 0x00, 0x10];
+        assert_eq!(encode_bytes(&data, ByteEncoding::Hex), "fbff0010");
+        assert_eq!(encode_bytes(&data, ByteEncoding::Base64), "+/8AEA==");
+        assert_eq!(encode_bytes(&data, ByteEncoding::Base64Url), "-_8AEA==");
+        assert_eq!(encode_bytes(&data, ByteEncoding::Base64UrlNoPad), "-_8AEA");
+        assert_eq!(encode_bytes(b"Hello World!", ByteEncoding::Base58), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(encode_bytes(&[0, 0, 1], ByteEncoding::Base58), "112");
+    }
+
+    #[test]
+    fn test_random_encoded() {
+        assert_eq!(random_encoded(32, ByteEncoding::Hex).len(), 64);
+        assert_eq!(random_encoded(32, ByteEncoding::Base64).len(), 44);
+        assert_eq!(random_encoded(32, ByteEncoding::Base64UrlNoPad).len(), 43);
+        assert_eq!(random_encoded(5, ByteEncoding::Base32).len(), 8);
+    }
+
+    #[test]
+    fn test_pronounceable_word() {
+        for _ in 0..200 {
+            let mut w = String::new();
+            push_pronounceable(&mut w, 4, &mut rand::thread_rng());
+            assert!((8..=12).contains(&w.len()), "{w}");
+            assert!(ONSETS.contains(&w.chars().next().unwrap()));
+            // Never two vowels in a row, never three consonants in a row.
+            let kinds: Vec<bool> = w.chars().map(|c| VOWELS.contains(&c)).collect();
+            assert!(!kinds.windows(2).any(|k| k[0] && k[1]), "{w}");
+            assert!(!kinds.windows(3).any(|k| !k[0] && !k[1] && !k[2]), "{w}");
+        }
+    }
+
+    #[test]
+    fn test_pronounceable_entropy() {
+        assert_eq!(pronounceable_entropy(0), 0.0);
+        let one = pronounceable_entropy(1);
+        // Between all-open (log2 80) and all-closed (log2 400) plus one bit.
+        assert!(one > 80f64.log2() && one < 400f64.log2() + 1.0);
+        assert!((pronounceable_entropy(4) - 4.0 * one).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_proquint_ipv4() {
+        // Examples from the proquint paper.
+        let vectors = [
+            ("127.0.0.1", "lusab-babad"),
+            ("63.84.220.193", "gutih-tugad"),
+            ("63.118.7.35", "gutuk-bisog"),
+            ("140.98.193.141", "mudof-sakat"),
+            ("64.255.6.200", "haguz-biram"),
+            ("128.30.52.45", "mabiv-gibot"),
+            ("147.67.119.2", "natag-lisaf"),
+            ("212.58.253.68", "tibup-zujah"),
+            ("216.35.68.215", "tobog-higil"),
+            ("216.68.232.21", "todah-vobij"),
+            ("198.81.129.136", "sinid-makam"),
+            ("12.110.110.204", "budov-kuras"),
+        ];
+        for (ip, quint) in vectors {
+            let addr: Ipv4Addr = ip.parse().unwrap();
+            assert_eq!(proquint_from_ipv4(addr), quint);
+            assert_eq!(ipv4_from_proquint(quint), Some(addr));
+        }
+    }
+
+    #[test]
+    fn test_proquint_round_trip() {
+        for value in [0, 1, 0xffff, 0x10000, u32::MAX] {
+            assert_eq!(proquint_decode(&proquint_encode(value)), Some(value));
+        }
+        for _ in 0..200 {
+            let s = proquint();
+            assert_eq!(proquint_encode(proquint_decode(&s).unwrap()), s);
+        }
+        assert_eq!(proquint_decode("LUSAB-BABAD"), Some(0x7f000001));
+        for bad in ["lusab", "lusab-baba", "lusab-babad-babad", "lusab-babae", "lusab_babad"] {
+            assert_eq!(proquint_decode(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_is_dns_label() {
+        for ok in ["a", "brave-fox-42", "r2d2", "x".repeat(63).as_str()] {
+            assert!(is_dns_label(ok), "{ok}");
+        }
+        let too_long = "x".repeat(64);
+        for bad in ["", "42-fox", "Brave-fox", "brave_fox", "brave.fox", "fox-", &too_long] {
+            assert!(!is_dns_label(bad), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_is_git_branch_name() {
+        for ok in ["brave-fox", "feature/brave_fox42", "Bold.Garden", "42-fox", "a/b/c"] {
+            assert!(is_git_branch_name(ok), "{ok}");
+        }
+        let bad = [
+            "", "@", "-fox", ".fox", "feature/.fox", "fox.", "fox..bar", "fox.lock",
+            "feature/fox.lock/x", "/fox", "fox/", "feature//fox", "fox@{1}", "fox bar",
+            "fox~1", "fox^", "fox:bar", "fox?", "fox*", "fox[1]", "fox\\bar", "fox\x7f",
+        ];
+        for b in bad {
+            assert!(!is_git_branch_name(b), "{b:?}");
+        }
+    }
+
+    #[test]
+    fn test_git_branch_style() {
+        for format in RandomFormat::all() {
+            if !is_wordlist(format) {
+                continue;
+            }
+            for _ in 0..100 {
+                let style = Style::for_target(Constraint::GitBranch);
+                let s = generate_with_style(format, &style);
+                assert!(is_git_branch_name(&s), "{format:?}: {s}");
+                assert!(!s.contains('.'), "{format:?}: {s}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dns_safe_style() {
+        let styled: Vec<RandomFormat> = RandomFormat::all()
+            .into_iter()
+            .filter(|f| f.default_length().is_none() && is_wordlist(*f))
+            .collect();
+        assert!(!styled.is_empty());
+        for format in styled {
+            for _ in 0..100 {
+                let s = generate_with_style(format, &Style::for_target(Constraint::Dns));
+                assert!(is_dns_label(&s), "{format:?}: {s}");
+            }
+        }
+        // Long enough to need truncating.
+        for _ in 0..50 {
+            let style = Style::for_target(Constraint::Dns);
+            let s = generate_with_length(RandomFormat::Pronounceable, &style, 30);
+            assert!(is_dns_label(&s), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_constraint_accepts() {
+        // (target, accepted, rejected)
+        let cases: &[(Constraint, &[&str], &[&str])] = &[
+            (
+                Constraint::RustIdent,
+                &["brave_fox", "BraveFox42", "_x"],
+                &["2fox", "brave-fox", "fn", "_"],
+            ),
+            (
+                Constraint::PythonIdent,
+                &["brave_fox", "Bold_garden42"],
+                &["9lives", "class", "a.b"],
+            ),
+            (
+                Constraint::JsIdent,
+                &["$brave", "braveFox", "_x1"],
+                &["1fox", "brave-fox", "class"],
+            ),
+            (
+                Constraint::SqlTable,
+                &["brave_fox", "fox42"],
+                &["BraveFox", "_fox", "2fox", "select"],
+            ),
+            (
+                Constraint::EnvVar,
+                &["BRAVE_FOX", "FOX_42"],
+                &["brave_fox", "BRAVE-FOX", "_FOX", "42"],
+            ),
+            (
+                Constraint::Filename,
+                &["brave-fox.txt", "Bold_garden42"],
+                &["-rf", ".hidden", "fox.", "con", "NUL.txt", "a/b", "a b"],
+            ),
+            (
+                Constraint::EmailLocal,
+                &["brave.fox", "fox+tag", "a_b-c"],
+                &[".fox", "fox.", "fox..bar", "a b", "a@b"],
+            ),
+        ];
+        for (constraint, good, bad) in cases {
+            for s in *good {
+                assert!(constraint.accepts(s), "{constraint:?} should accept {s:?}");
+            }
+            for s in *bad {
+                assert!(!constraint.accepts(s), "{constraint:?} should reject {s:?}");
+            }
+        }
+        assert!(Constraint::EmailLocal.accepts(&"x".repeat(64)));
+        assert!(!Constraint::EmailLocal.accepts(&"x".repeat(65)));
+    }
+
+    #[test]
+    fn test_constrained_styles() {
+        for constraint in Constraint::all() {
+            for format in RandomFormat::all() {
+                if !is_wordlist(format) {
+                    continue;
+                }
+                for _ in 0..50 {
+                    let s = generate_with_style(format, &Style::for_target(constraint));
+                    assert!(constraint.accepts(&s), "{constraint:?} {format:?}: {s}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_styled() {
+        for format in RandomFormat::all() {
+            let styled = is_wordlist(format) || format == RandomFormat::Pronounceable;
+            assert_eq!(format.is_styled(), styled, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_with_prefix() {
+        let long = format!("{}-", "a".repeat(44));
+        let style = Style::for_target(Constraint::Dns).with_prefix(&long).unwrap();
+        let mut generator = Generator::new(RandomFormat::MemorableName, style, 0);
+        for _ in 0..2000 {
+            let value = generator.next_str();
+            assert!(value.starts_with(&long) && is_dns_label(value), "{value}");
+        }
+        let branch = Style::for_target(Constraint::GitBranch).with_prefix("feature/").unwrap();
+        let value = generate_with_style(RandomFormat::FoodCombination, &branch);
+        assert!(value.starts_with("feature/") && is_git_branch_name(&value), "{value}");
+        // The prefix isn't leeted or mistaken for part of a PIN.
+        let zeros = Style::random().with_prefix("00").unwrap();
+        let pin = generate_with_style(RandomFormat::Pin, &zeros);
+        assert!(pin.starts_with("00") && !is_weak_pin(&pin[2..]), "{pin}");
+
+        let dns = || Style::for_target(Constraint::Dns);
+        assert_eq!(dns().with_prefix("Web-").unwrap_err(), PrefixError::Invalid(Constraint::Dns));
+        assert_eq!(
+            dns().with_prefix(&"a".repeat(56)).unwrap_err(),
+            PrefixError::TooLong(Constraint::Dns, DNS_LABEL_MAX)
+        );
+        for bad in ["feature//", ".hidden/", "x.lock/", "a..b/", "-x/"] {
+            let style = Style::for_target(Constraint::GitBranch);
+            assert!(style.with_prefix(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_render_non_ascii_words() {
+        assert_eq!(fold_ascii("Café"), "cafe");
+        assert_eq!(fold_ascii("Straße"), "strasse");
+        assert_eq!(fold_ascii("東京"), "");
+        for constraint in Constraint::all() {
+            let style = Style::for_target(constraint);
+            let out = render_words(&["café", "東京", "naïve"], &style).unwrap();
+            assert!(constraint.accepts(&out.text), "{constraint:?}: {}", out.text);
+            if !constraint.accepts("東京") {
+                let err = render_words(&["東京"], &style).unwrap_err();
+                assert_eq!(err, RenderError(constraint));
+            }
+        }
+        // Unconstrained output keeps the words as given.
+        let out = render_words(&["café"], &Style::plain(TagKind::Digits(1))).unwrap();
+        assert!(out.text.contains("café"));
+        // A zero-syllable word would leave only the tag.
+        let dns = Style::for_target(Constraint::Dns);
+        let word = generate_with_length(RandomFormat::Pronounceable, &dns, 0);
+        assert!(is_dns_label(&word), "{word}");
+    }
+
+    #[test]
+    fn test_output_space() {
+        let style = Style::plain(TagKind::Digits(1));
+        assert_eq!(output_space(RandomFormat::RhymingPair, &style, 0), 210.0);
+        // 26 phonetic words, two or three of them.
+        let phonetic = 26f64.powi(2) + 26f64.powi(3);
+        assert_eq!(output_space(RandomFormat::PhoneticAlphabet, &style, 0), phonetic * 10.0);
+        // LOCATIONS lists "amazon" twice.
+        let geo = output_space(RandomFormat::GeographicName, &style, 0);
+        assert_eq!(geo, (LOCATIONS.len() - 1) as f64 * 10.0);
+        assert_eq!(output_space(RandomFormat::Bytes, &style, 2), 65536.0);
+    }
+
+    #[test]
+    fn test_generate_unique() {
+        let style = Style::plain(TagKind::Digits(1));
+        // The whole space is reachable...
+        let all = generate_unique(RandomFormat::RhymingPair, &style, 210).unwrap();
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 210);
+        // ...and one more is refused up front rather than looping.
+        assert_eq!(
+            generate_unique(RandomFormat::RhymingPair, &style, 211),
+            Err(UniqueError::SpaceTooSmall { requested: 211, space: 210 })
+        );
+        let pins = generate_unique_with_length(RandomFormat::Pin, &style, 50, 4).unwrap();
+        assert_eq!(pins.iter().collect::<HashSet<_>>().len(), 50);
+
+        // Other formats' values in `issued` don't shrink the space...
+        let uuids: HashSet<String> = (0..500).map(|_| uuid()).collect();
+        let pins = generate_unique_excluding(RandomFormat::Pin, &style, 50, 4, &uuids).unwrap();
+        assert_eq!(pins.len(), 50);
+        // ...but this format's own do, once they've used it up.
+        let issued: HashSet<String> = all.into_iter().skip(10).collect();
+        assert_eq!(
+            generate_unique_excluding(RandomFormat::RhymingPair, &style, 11, 0, &issued),
+            Err(UniqueError::Exhausted { requested: 11, found: 10 })
+        );
+    }
+
+    #[test]
+    fn test_collision_probability() {
+        // The classic birthday problem: 23 people, 365 days.
+        assert!((collision_probability(365.0, 23) - 0.5).abs() < 0.01);
+        assert_eq!(collision_probability(365.0, 1), 0.0);
+        // Not -0.0, which would print as "-0.00e0".
+        assert!(collision_probability(365.0, 0).is_sign_positive());
+        // Tiny probabilities stay accurate rather than rounding to zero.
+        let p = collision_probability(2f64.powi(122), 1_000_000);
+        assert!(p > 0.0 && p < 1e-20);
+    }
+
+    #[test]
+    fn test_collision_space() {
+        // Uniform tags: effective space is the whole space.
+        let style = Style::plain(TagKind::Hex(4));
+        let full = output_space(RandomFormat::ConstellationName, &style, 0);
+        let effective = collision_space(RandomFormat::ConstellationName, &style, 0);
+        assert!((effective - full).abs() < 1e-6 * full);
+        // Small ints favour short numbers, and "amazon" is listed twice.
+        let style = Style::plain(TagKind::SmallInt);
+        assert!(collision_space(RandomFormat::ConstellationName, &style, 0) < 26.0 * 9999.0);
+        let style = Style::plain(TagKind::Digits(2));
+        assert!(collision_space(RandomFormat::GeographicName, &style, 0) < 41.0 * 100.0);
+        // Non-styled formats are uniform.
+        assert_eq!(collision_bits(RandomFormat::ApiKey, &style, 10), 10.0 * 36f64.log2());
+    }
+
+    #[test]
+    fn test_cheapest() {
+        let best = cheapest(100_000, 1e-6).unwrap();
+        assert!(best.probability(100_000) <= 1e-6);
+        // Anything shorter would have failed the bound.
+        for format in RandomFormat::all() {
+            for c in candidates(format, format.default_length().unwrap_or(0)) {
+                if c.mean_chars < best.mean_chars {
+                    assert!(c.probability(100_000) > 1e-6, "{:?}", c);
+                }
+            }
+        }
+        assert!(cheapest(u64::MAX, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_push_charset() {
+        fn check(charset: &[u8], make: &mut dyn FnMut(usize) -> String) {
+            for length in [0, 1, 24, 63, 64, 65, 200] {
+                let s = make(length);
+                assert_eq!(s.len(), length);
+                assert!(s.bytes().all(|b| charset.contains(&b)), "{s}");
+            }
+            // Every character turns up.
+            let seen: HashSet<u8> = make(charset.len() * 200).bytes().collect();
+            assert_eq!(seen.len(), charset.len());
+        }
+        let mut rng = StdRng::seed_from_u64(1);
+        check(b"x", &mut |n| from_charset(n, b"x", &mut rng));
+        check(DIGITS, &mut |n| from_charset(n, DIGITS, &mut rng));
+        check(b"0123456789abcdef", &mut |n| from_charset(n, b"0123456789abcdef", &mut rng));
+        check(API_KEY_CHARS, &mut |n| from_charset(n, API_KEY_CHARS, &mut rng));
+        check(URL_SAFE_CHARS, &mut |n| from_charset(n, URL_SAFE_CHARS, &mut rng));
+    }
+
+    #[test]
+    fn test_generator() {
+        let style = Style::random();
+        for format in RandomFormat::all() {
+            let length = format.default_length().unwrap_or(0);
+            let mut generator = Generator::new(format, style.clone(), length);
+            let value = generator.next_styled().clone();
+            assert!(!value.text.is_empty(), "{format:?}");
+            assert_eq!(value.spans().map(|(_, s)| s).collect::<String>(), value.text);
+            assert_ne!(generator.next_str(), "");
+        }
+
+        // The same seed gives the same values (v7 also reads the clock).
+        let seeded = |seed| {
+            let rng = StdRng::seed_from_u64(seed);
+            let format = RandomFormat::MemorableName;
+            let mut generator = Generator::with_rng(format, style.clone(), 0, rng);
+            (0..20).map(|_| generator.next_str().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(seeded(7), seeded(7));
+        assert_ne!(seeded(7), seeded(8));
+
+        // `bytes` is hex whatever the style, unless told otherwise.
+        for _ in 0..20 {
+            let mut generator = Generator::new(RandomFormat::Bytes, Style::random(), 4);
+            let hex = generator.next_str();
+            assert!(hex.len() == 8 && hex.chars().all(|c| c.is_ascii_hexdigit()), "{hex}");
+        }
+        let mut generator = Generator::new(RandomFormat::Bytes, style.clone(), 5)
+            .with_encoding(ByteEncoding::Base32);
+        assert_eq!(generator.next_str().len(), 8);
+        let items = generator.next_unique(3, &HashSet::new()).unwrap();
+        assert!(items.iter().all(|item| item.text.len() == 8));
+
+        // Reused buffers encode exactly what the one-off helpers do.
+        for encoding in ByteEncoding::all() {
+            let mut rng = StdRng::seed_from_u64(5);
+            let format = RandomFormat::Bytes;
+            let mut generator = Generator::with_rng(format, style.clone(), 9, rng.clone())
+                .with_encoding(encoding);
+            for _ in 0..3 {
+                let expected = encode_bytes(&random_bytes(9, &mut rng), encoding);
+                assert_eq!(generator.next_str(), expected, "{encoding:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_v7_blocks() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut ids = Vec::new();
+        let mut take = |clock: &mut V7Clock| {
+            let mut block = clock.reserve(1000);
+            ids.extend((0..1000).map(|_| block.next(&mut rng)));
+        };
+        let mut clock = V7Clock::new();
+        take(&mut clock);
+        take(&mut clock);
+        // A nearly full counter moves on to the next millisecond.
+        clock.counter = (1 << V7_COUNTER_BITS) - 10;
+        take(&mut clock);
+        take(&mut clock);
+
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(ids.iter().all(|id| id.get_version_num() == 7));
+        let strings: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        assert!(strings.windows(2).all(|w| w[0] < w[1]));
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let (secs, nanos) = ids[0].get_timestamp().unwrap().to_unix();
+        assert!(now.abs_diff(secs * 1000 + nanos as u64 / 1_000_000) < 1000);
+    }
+
+    #[test]
+    fn test_stream() {
+        let style = Style::random();
+        let keys: Vec<String> = stream(RandomFormat::ApiKey, style.clone(), 20).take(1000).collect();
+        assert_eq!(keys.len(), 1000);
+        assert!(keys.iter().all(|k| k.len() == 20));
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 1000);
+
+        let mut names = stream(RandomFormat::MemorableName, style, 0);
+        assert!(names.by_ref().take(5).all(|name| !name.is_empty()));
+        assert!(names.next().is_some());
+    }
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("foobar", false), "\x1b]52;c;Zm9vYmFy\x07");
+        assert_eq!(osc52("", false), "\x1b]52;c;\x07");
+        assert_eq!(
+            osc52("foobar", true),
+            "\x1bPtmux;\x1b\x1b]52;c;Zm9vYmFy\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_generate_styled() {
+        let style = Style::plain(TagKind::Digits(4));
+        let item = generate_styled(RandomFormat::MemorableName, &style, 0);
+        let spans: Vec<(Part, &str)> = item.spans().collect();
+        assert_eq!(spans.iter().map(|(_, s)| *s).collect::<String>(), item.text);
+        let (part, tag) = spans.last().unwrap();
+        assert_eq!(*part, Part::Tag);
+        assert!(tag.len() == 4 && tag.chars().all(|c| c.is_ascii_digit()));
+        assert!(spans.iter().filter(|(p, _)| *p == Part::Sep).all(|(_, s)| *s == "-"));
+        assert!(spans.iter().any(|(p, _)| *p == Part::Word));
+
+        // Parts still cover the text after a constraint truncates it.
+        let style = Style::for_target(Constraint::Dns);
+        for _ in 0..200 {
+            let item = generate_styled(RandomFormat::HistoricalFigure, &style, 0);
+            assert_eq!(item.spans().map(|(_, s)| s).collect::<String>(), item.text);
+        }
+
+        let mut item = Styled::from("019e62bd".to_string());
+        item.prepend(Part::Plain, "id-");
+        assert_eq!(item.text, "id-019e62bd");
+        assert_eq!(
+            item.spans().collect::<Vec<_>>(),
+            [(Part::Plain, "id-"), (Part::Plain, "019e62bd")]
+        );
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("uuidv7".parse(), Ok(RandomFormat::UuidV7));
+        assert_eq!("UUID".parse(), Ok(RandomFormat::Uuid));
+        assert_eq!("uuid7".parse(), Ok(RandomFormat::UuidV7));
+        assert_eq!("adj-noun".parse(), Ok(RandomFormat::MemorableName));
+        assert_eq!("const".parse(), Ok(RandomFormat::ConstellationName));
+        assert_eq!("pron".parse(), Ok(RandomFormat::Pronounceable));
+
+        match "u".parse::<RandomFormat>() {
+            Err(ParseFormatError::Ambiguous { candidates, .. }) => {
+                assert!(candidates.contains(&RandomFormat::Uuid));
+                assert!(candidates.contains(&RandomFormat::UrlSafe));
+            }
+            other => panic!("expected ambiguous, got {:?}", other),
+        }
+        let err = "constelation".parse::<RandomFormat>().unwrap_err();
+        assert_eq!(
+            err,
+            ParseFormatError::Unknown {
+                input: "constelation".to_string(),
+                suggestion: Some(RandomFormat::ConstellationName),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "unknown format \"constelation\" (did you mean constellation?)"
+        );
+        assert_eq!(RandomFormat::closest("xyzzyplugh"), None);
+        assert!("".parse::<RandomFormat>().is_err());
+    }
+
+    #[test]
+    fn test_aliases_are_unique() {
+        let mut seen = HashSet::new();
+        for format in RandomFormat::all() {
+            let names = std::iter::once(format.short_name()).chain(format.aliases().iter().copied());
+            for name in names {
+                assert!(seen.insert(name), "{} used twice", name);
+            }
+        }
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_value_enum() {
+        use clap::ValueEnum;
+        let parse = <RandomFormat as ValueEnum>::from_str;
+        assert_eq!(parse("uuid7", true), Ok(RandomFormat::UuidV7));
+        for format in RandomFormat::value_variants() {
+            assert_eq!(parse(format.short_name(), false), Ok(*format));
+        }
+    }
 }
@@ -1,18 +1,405 @@
-use clap::Parser;
+mod bulk;
+mod last;
+mod layout;
+mod pager;
+mod picker;
+
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
+use clap::error::{ContextKind, ContextValue};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use layout::{Fill, Grid};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use randr::{self, RandomFormat, Style};
-use terminal_size::{terminal_size, Width};
+use rand::{Rng, SeedableRng};
+use randr::ledger::{self, Ledger};
+use randr::{
+    self, ByteEncoding, Constraint, Generator, ParseFormatError, Part, RandomFormat, Style, Styled,
+    TagKind,
+};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use terminal_size::{terminal_size, Height, Width};
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Specific format to generate. Omit to get the themed menu.
-    format: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Without a subcommand, `randr` generates: `randr name` is `randr gen name`.
+    #[command(flatten)]
+    gen: GenArgs,
+}
+
+#[derive(Args)]
+struct GenArgs {
+    /// Formats or menu themes to generate. One format gives a plain grid;
+    /// anything else gives a section each, like the menu. A name that is
+    /// both (`character`) means the format; use --theme for the theme.
+    /// Omit to get the whole themed menu.
+    #[arg(value_name = "FORMAT|THEME", value_parser = NameParser { themes: true })]
+    formats: Vec<String>,
+
+    /// Menu themes to show, comma-separated.
+    #[arg(long, value_delimiter = ',', value_parser = theme_names())]
+    theme: Vec<&'static str>,
 
-    /// Number of items (only applies to format-specific mode; default fills
-    /// about 5 rows at your terminal width).
+    /// Number of items per format, or rows per theme section. A single
+    /// format defaults to a screenful, sections to a few rows each.
     #[arg(short = 'n', long)]
     count: Option<usize>,
+
+    /// Output length for sized formats: characters for url/api/pin (at
+    /// least 4 for pin), bytes for totp/bytes, syllables for word. Other
+    /// formats ignore it.
+    #[arg(short = 'l', long)]
+    length: Option<usize>,
+
+    /// Encoding for `bytes`; hex unless given.
+    #[arg(
+        short = 'e',
+        long,
+        value_parser = NameTable::new(ByteEncoding::all(), ByteEncoding::short_name)
+    )]
+    encoding: Option<ByteEncoding>,
+
+    /// Only produce valid DNS labels from the memorable formats (lowercase,
+    /// `-` only, letter first, at most 63 chars) — for hostnames, k8s
+    /// resource names and S3 buckets. Same as `--for dns`.
+    #[arg(long, conflicts_with = "target")]
+    dns: bool,
+
+    /// Keep word formats valid for a target system. Formats with an
+    /// alphabet of their own (uuid, api, bytes, ...) are refused.
+    #[arg(
+        short = 't',
+        long = "for",
+        visible_alias = "target",
+        value_parser = NameTable::new(Constraint::all(), Constraint::short_name)
+    )]
+    target: Option<Constraint>,
+
+    /// Never repeat a value within one run. Fails instead of looping when
+    /// the format/style can't produce that many distinct values.
+    #[arg(short = 'u', long)]
+    unique: bool,
+
+    /// Prepend this to every item, e.g. `feature/`. With `--for`, it's
+    /// checked once and counts towards the target's length limit.
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Record every value in a ledger and never issue the same one twice,
+    /// across runs. Uses $XDG_DATA_HOME/randr/ledger.tsv unless given
+    /// `--ledger=PATH`.
+    #[arg(long, num_args = 0..=1, require_equals = true, value_name = "PATH")]
+    ledger: Option<Option<PathBuf>>,
+
+    /// Ledger namespace to check against and record in.
+    #[arg(long, default_value = ledger::DEFAULT_NAMESPACE)]
+    namespace: String,
+
+    /// Browse the menu (or the given format) in the terminal: arrows move,
+    /// `r` rerolls a section, `p` pins its style, Enter prints the item.
+    #[arg(short = 'i', long)]
+    interactive: bool,
+
+    /// Also put an item on the clipboard through the terminal (OSC 52, so
+    /// it works over SSH and in tmux): the picked one with `-i`, otherwise
+    /// the item with label ITEM (`b3`) or number ITEM in printed order,
+    /// counting from 1 (default 1).
+    #[arg(long, num_args = 0..=1, require_equals = true, value_name = "ITEM")]
+    copy: Option<Option<String>>,
+
+    /// Label each grid item (`a1`, `b3`: section letter, then position) so
+    /// it can be fetched again with `randr last b3`.
+    #[arg(short = 'L', long)]
+    labels: bool,
+
+    /// Colour the grid: tags and separators stand out from words, headings
+    /// show each section's entropy. `auto` colours only on a terminal and
+    /// when NO_COLOR isn't set.
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Fill grids row by row, or down each column first like `ls`.
+    #[arg(long, value_enum, default_value_t = Fill::Rows)]
+    fill: Fill,
+
+    /// Print everything at once even when it's taller than the terminal,
+    /// instead of paging through $PAGER or the built-in pager.
+    #[arg(long)]
+    no_pager: bool,
+
+    /// With several formats, render every section in one shared style
+    /// instead of rolling one per section.
+    #[arg(long)]
+    same_style: bool,
+
+    /// Write one value per line as it's generated instead of a grid, until
+    /// `-n` values or until the reader closes the pipe (`| head`). For
+    /// millions of IDs; takes a single format.
+    #[arg(long, conflicts_with_all = ["interactive", "copy", "labels", "unique", "ledger"])]
+    stream: bool,
+
+    /// Generate a `--stream` on N threads (0: one per core). Output is the
+    /// same as with one thread, in the same order.
+    #[arg(short = 'j', long, default_value_t = 1, value_name = "N", requires = "stream")]
+    jobs: usize,
+
+    /// Make `--stream` reproducible: the same seed gives the same style and
+    /// values, whatever `--jobs` is. `uuidv7` timestamps still come from
+    /// the clock. Not for secrets — anyone with the seed has the values.
+    #[arg(long, requires = "stream")]
+    seed: Option<u64>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl GenArgs {
+    fn constraint(&self) -> Option<Constraint> {
+        self.target.or(self.dns.then_some(Constraint::Dns))
+    }
+
+    fn color(&self) -> bool {
+        match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Passes names through untouched (`requested_sections` and `find_format`
+/// resolve prefixes and explain typos) but tells clap the full names, so help
+/// and shell completions can list them.
+#[derive(Clone)]
+struct NameParser {
+    themes: bool,
+}
+
+impl TypedValueParser for NameParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<String, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let formats = RandomFormat::value_variants().iter().filter_map(|f| f.to_possible_value());
+        // `character` is both; the name means the format.
+        let themes = THEMES
+            .iter()
+            .filter(|(theme, _)| self.themes && RandomFormat::from_name(theme).is_none())
+            .map(|(theme, _)| PossibleValue::new(*theme).help("menu theme"));
+        Some(Box::new(formats.chain(themes)))
+    }
+}
+
+/// One of a fixed table of names, matched case-insensitively. Errors, help
+/// and shell completions all list the same names.
+#[derive(Clone)]
+struct NameTable<T> {
+    entries: Vec<(String, T)>,
+}
+
+impl<T> NameTable<T> {
+    fn new<N: Into<String>>(values: Vec<T>, name: impl Fn(&T) -> N) -> Self {
+        let entries = values.into_iter().map(|value| (name(&value).into(), value)).collect();
+        NameTable { entries }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> TypedValueParser for NameTable<T> {
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<T, clap::Error> {
+        let given = StringValueParser::new().parse_ref(cmd, arg, value)?;
+        let wanted = given.to_lowercase();
+        if let Some((_, value)) = self.entries.iter().find(|(name, _)| *name == wanted) {
+            return Ok(value.clone());
+        }
+        let mut err = clap::Error::new(clap::error::ErrorKind::InvalidValue).with_cmd(cmd);
+        let arg = arg.map_or_else(|| "...".to_string(), |arg| arg.to_string());
+        err.insert(ContextKind::InvalidArg, ContextValue::String(arg));
+        err.insert(ContextKind::InvalidValue, ContextValue::String(given));
+        let names = self.entries.iter().map(|(name, _)| name.clone()).collect();
+        err.insert(ContextKind::ValidValue, ContextValue::Strings(names));
+        Err(err)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(self.entries.iter().map(|(name, _)| PossibleValue::new(name.clone()))))
+    }
+}
+
+fn theme_names() -> NameTable<&'static str> {
+    NameTable::new(THEMES.iter().map(|(theme, _)| *theme).collect(), |theme| *theme)
+}
+
+
+fn parse_secret_length(s: &str) -> Result<usize, String> {
+    let bytes: usize = s.parse().map_err(|e| format!("{}", e))?;
+    if bytes < randr::TOTP_SECRET_MIN_BYTES {
+        return Err(format!("at least {} bytes (128 bits)", randr::TOTP_SECRET_MIN_BYTES));
+    }
+    Ok(bytes)
+}
+
+fn parse_probability(s: &str) -> Result<f64, String> {
+    let p: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if !(p > 0.0 && p < 1.0) {
+        return Err("expected a probability between 0 and 1, exclusive".to_string());
+    }
+    Ok(p)
+}
+
+
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate formats or menu sections. The default when no subcommand
+    /// is given.
+    Gen(GenArgs),
+
+    /// Print a completion script for SHELL.
+    ///
+    /// e.g. `randr completions bash > ~/.local/share/bash-completion/completions/randr`
+    Completions {
+        shell: Shell,
+    },
+
+    /// Print the man page (roff).
+    ///
+    /// e.g. `randr man > ~/.local/share/man/man1/randr.1`
+    Man,
+
+    /// Print an otpauth:// URI for a fresh (or given) TOTP secret.
+    Otp {
+        /// Service name shown in the authenticator app.
+        #[arg(long)]
+        issuer: String,
+
+        /// Account label, usually a username or email.
+        #[arg(long)]
+        account: String,
+
+        /// Use this base32 secret instead of generating one.
+        #[arg(long)]
+        secret: Option<String>,
+
+        /// Size of the generated secret in bytes, at least 16 (RFC 4226).
+        #[arg(
+            short = 'l',
+            long,
+            default_value_t = randr::TOTP_SECRET_BYTES,
+            value_parser = parse_secret_length
+        )]
+        length: usize,
+
+        /// Digits per code, 6 to 8.
+        #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+        digits: u32,
+
+        /// Also print the code for the current time step, to check the
+        /// authenticator agrees.
+        #[arg(long)]
+        code: bool,
+    },
+
+    /// Chance of a repeat among COUNT values of a format, per tag shape,
+    /// and the cheapest format that stays under --max-p.
+    Collide {
+        /// Format to check.
+        #[arg(value_parser = NameParser { themes: false })]
+        format: String,
+
+        /// How many values you'll generate.
+        #[arg(short = 'n', long)]
+        count: u64,
+
+        /// Only show this tag shape.
+        #[arg(long, value_parser = NameTable::new(TagKind::all(), TagKind::short_name))]
+        tag: Option<TagKind>,
+
+        /// Length for sized formats.
+        #[arg(short = 'l', long)]
+        length: Option<usize>,
+
+        /// Acceptable collision probability for the suggestion, between 0
+        /// and 1.
+        #[arg(long, default_value_t = 1e-6, value_parser = parse_probability)]
+        max_p: f64,
+    },
+
+    /// Print an item from the last grid by its label (`b3`), or every
+    /// label and item when none is given.
+    Last {
+        item: Option<String>,
+    },
+
+    /// Inspect or edit the ledger of issued values (see `--ledger`).
+    Ledger {
+        /// Ledger file. Defaults to $XDG_DATA_HOME/randr/ledger.tsv.
+        #[arg(long, global = true)]
+        path: Option<PathBuf>,
+
+        #[command(subcommand)]
+        action: LedgerAction,
+    },
+}
+
+
+#[derive(Subcommand)]
+enum LedgerAction {
+    /// Show namespaces with their counts, or the values in one namespace.
+    List {
+        #[arg(long)]
+        namespace: Option<String>,
+    },
+
+    /// Let values be issued again.
+    Forget {
+        #[arg(long, default_value = ledger::DEFAULT_NAMESPACE)]
+        namespace: String,
+
+        /// Values to forget.
+        #[arg(required_unless_present = "all")]
+        values: Vec<String>,
+
+        /// Forget the whole namespace.
+        #[arg(long, conflicts_with = "values")]
+        all: bool,
+    },
+
+    /// Dump entries as tab-separated namespace, value, issued-at (unix
+    /// seconds).
+    Export {
+        #[arg(long)]
+        namespace: Option<String>,
+    },
 }
 
 /// Fixed themes shown on every menu run. Each section picks one format from
@@ -36,112 +423,995 @@ const THEMES: &[(&str, &[RandomFormat])] = &[
     ),
 ];
 
+/// One section asked for on the command line: a menu theme, which rolls a
+/// format from its pool, or a single format.
+#[derive(Clone, Copy)]
+enum Section {
+    Theme(&'static str, &'static [RandomFormat]),
+    Format(RandomFormat),
+}
+
+impl Section {
+    fn theme(name: &str) -> Option<Section> {
+        THEMES
+            .iter()
+            .find(|(theme, _)| *theme == name)
+            .map(|(theme, pool)| Section::Theme(theme, pool))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Section::Theme(theme, _) => theme,
+            Section::Format(format) => format.short_name(),
+        }
+    }
+
+    fn includes(&self, format: RandomFormat) -> bool {
+        match self {
+            Section::Theme(_, pool) => pool.contains(&format),
+            Section::Format(section) => *section == format,
+        }
+    }
+
+    fn is_styled(&self) -> bool {
+        match self {
+            Section::Theme(_, pool) => pool.iter().all(RandomFormat::is_styled),
+            Section::Format(format) => format.is_styled(),
+        }
+    }
+}
+
 /// Cap how tall any one section can get. Items per section = cols × this,
 /// where cols is whatever fits the format at the terminal width — so wide
 /// formats (UUID) get a few candidates, narrow ones get more, but both
 /// stay short vertically.
 const MAX_ROWS_PER_SECTION: usize = 2;
+/// Rows `randr <format>` fills when no `-n` is given and the terminal's
+/// height is unknown (output piped, say).
+const FORMAT_ROWS: usize = 3;
 const COL_GAP: usize = 2;
+const MENU_INDENT: usize = 2;
+/// `--stream` writes in chunks this big: fewer syscalls than the 8 KiB
+/// default once values come out at tens of millions a second.
+const STREAM_BUFFER: usize = 256 * 1024;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
 
 fn term_width() -> usize {
     terminal_size().map(|(Width(w), _)| w as usize).unwrap_or(80)
 }
 
-/// Print a heading + grid of items. Items are assumed to share roughly the
-/// same width (because the same Style was used to produce them).
-fn print_grid(items: &[String], indent: usize, width: usize) {
-    let max = items.iter().map(|s| s.chars().count()).max().unwrap_or(0);
-    let cols = std::cmp::max(1, width.saturating_sub(indent) / (max + COL_GAP));
-    let pad = " ".repeat(indent);
-    for chunk in items.chunks(cols) {
-        let line: Vec<String> = chunk
-            .iter()
-            .map(|s| format!("{:<w$}", s, w = max))
-            .collect();
-        println!("{}{}", pad, line.join(&" ".repeat(COL_GAP)));
+fn term_height() -> Option<usize> {
+    terminal_size().map(|(_, Height(h))| h as usize)
+}
+
+/// Print a heading + grid of items. Each column is as wide as its widest
+/// item. With `section` set, each cell is prefixed with its label (`b3`);
+/// with `color`, cells are painted by `paint`.
+fn print_grid(
+    items: &[Styled],
+    section: Option<usize>,
+    indent: usize,
+    width: usize,
+    fill: Fill,
+    color: bool,
+) {
+    for line in grid_lines(items, section, indent, width, fill, color) {
+        println!("{}", line);
     }
 }
 
-fn generate_batch(format: RandomFormat, style: &Style, n: usize) -> Vec<String> {
-    (0..n).map(|_| randr::generate_with_style(format, style)).collect()
+/// Terminal columns `s` takes up: wide (CJK) characters count two, combining
+/// marks and zero-width joiners nothing.
+fn display_width(s: &str) -> usize {
+    s.width()
+}
+
+fn grid_lines(
+    items: &[Styled],
+    section: Option<usize>,
+    indent: usize,
+    width: usize,
+    fill: Fill,
+    color: bool,
+) -> Vec<String> {
+    let label_w = section.map_or(0, |s| last::label(s, items.len().saturating_sub(1)).len() + 1);
+    let widths: Vec<usize> = items.iter().map(|s| display_width(&s.text) + label_w).collect();
+    let grid = Grid::fit(&widths, width.saturating_sub(indent), COL_GAP, fill);
+    let pad = " ".repeat(indent);
+    grid.rows()
+        .iter()
+        .map(|row| {
+            let mut line = pad.clone();
+            for (c, &i) in row.iter().enumerate() {
+                if c > 0 {
+                    // Pad the previous cell out to its column's width.
+                    let prev = row[c - 1];
+                    let col = grid.position(prev).1;
+                    line.push_str(&" ".repeat(grid.widths[col] - widths[prev] + COL_GAP));
+                }
+                let label = match section {
+                    Some(s) => format!("{:<w$}", last::label(s, i), w = label_w),
+                    None => String::new(),
+                };
+                let item = &items[i];
+                if color && !label.is_empty() {
+                    line.push_str(&format!("{}{}{}{}", DIM, label, RESET, paint(item)));
+                } else if color {
+                    line.push_str(&paint(item));
+                } else {
+                    line.push_str(&format!("{}{}", label, item.text));
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+/// ANSI-coloured `item`: tags in cyan, separators dimmed, words as-is.
+fn paint(item: &Styled) -> String {
+    item.spans()
+        .map(|(part, s)| match part {
+            Part::Tag => format!("{}{}{}", CYAN, s, RESET),
+            Part::Sep => format!("{}{}{}", DIM, s, RESET),
+            Part::Word | Part::Plain => s.to_string(),
+        })
+        .collect()
+}
+
+/// Generate `n` items. With `exclude` set, items are distinct from each
+/// other and from everything in it (`--unique` / `--ledger`).
+fn generate_batch(
+    format: RandomFormat,
+    style: &Style,
+    n: usize,
+    args: &GenArgs,
+    exclude: Option<&HashSet<String>>,
+) -> Vec<Styled> {
+    let length = args.length.or(format.default_length()).unwrap_or(0);
+    let mut generator = Generator::with_rng(format, style.clone(), length, rand::thread_rng())
+        .with_encoding(args.encoding.unwrap_or_default());
+    if let Some(issued) = exclude {
+        return match generator.next_unique(n, issued) {
+            Ok(items) => items,
+            Err(e) => {
+                eprintln!("{} ({}): {}", format.short_name(), n, e);
+                std::process::exit(1);
+            }
+        };
+    }
+    (0..n).map(|_| generator.next_styled().clone()).collect()
 }
 
 /// Rough estimate of an item's width so we can size sections before
 /// generating. Slight under/over-estimation is fine — the grid uses the
 /// actual measured width when laying out.
-fn estimated_width(format: RandomFormat) -> usize {
+fn estimated_width(format: RandomFormat, length: Option<usize>) -> usize {
     match format {
         RandomFormat::Uuid | RandomFormat::UuidV7 => 36,
-        RandomFormat::ApiKey => 24,
-        RandomFormat::UrlSafe => 16,
-        _ => 22,
+        RandomFormat::Proquint => 11,
+        RandomFormat::TotpSecret => (length.unwrap_or(randr::TOTP_SECRET_BYTES) * 8).div_ceil(5),
+        RandomFormat::Bytes => length.unwrap_or(randr::BYTES_LENGTH) * 2,
+        RandomFormat::Pronounceable => length.unwrap_or(randr::SYLLABLES) * 3 + 4,
+        _ => length.or(format.default_length()).unwrap_or(22),
     }
 }
 
-fn print_themed_menu() {
-    let mut rng = rand::thread_rng();
-    let width = term_width();
-    let indent = 2;
-
-    for (theme, pool) in THEMES {
-        let format = *pool.choose(&mut rng).unwrap();
-        let style = Style::random();
-
-        // Generate a starter batch using the format's estimated width, then
-        // re-measure actual content and grow the batch to fill the row at
-        // that real width. Keeps narrow formats from showing a half-empty row.
-        let est = estimated_width(format);
-        let est_cols = std::cmp::max(1, width.saturating_sub(indent) / (est + COL_GAP));
-        let mut items = generate_batch(format, &style, est_cols * MAX_ROWS_PER_SECTION);
-
-        let actual_max = items.iter().map(|s| s.chars().count()).max().unwrap_or(est);
-        let actual_cols =
-            std::cmp::max(1, width.saturating_sub(indent) / (actual_max + COL_GAP));
-        let target = actual_cols * MAX_ROWS_PER_SECTION;
-        if target > items.len() {
-            items.extend(
-                (items.len()..target).map(|_| randr::generate_with_style(format, &style)),
-            );
-        } else {
-            items.truncate(target);
+fn section_style(args: &GenArgs) -> Style {
+    section_style_with(args, &mut rand::thread_rng())
+}
+
+/// `section_style`, rolled from `rng`.
+fn section_style_with(args: &GenArgs, rng: &mut impl Rng) -> Style {
+    let style = match args.constraint() {
+        Some(constraint) => Style::for_target_with(constraint, rng),
+        None => Style::random_with(rng),
+    };
+    // The style puts `--prefix` on and fits each value around it, so no
+    // value can come out invalid once the prefix itself has passed.
+    let prefix = args.prefix.as_deref().unwrap_or("");
+    style.with_prefix(prefix).unwrap_or_else(|e| {
+        eprintln!("prefix {:?} {}", prefix, e);
+        std::process::exit(1);
+    })
+}
+
+/// The `--ledger` for this run: locked for the whole run, plus what's
+/// already been issued in the namespace.
+struct LedgerRun {
+    ledger: Ledger,
+    issued: HashSet<String>,
+}
+
+fn ledger_path(path: Option<&PathBuf>) -> PathBuf {
+    match path.cloned().or_else(ledger::default_path) {
+        Some(path) => path,
+        None => {
+            eprintln!("can't find a data directory for the ledger; pass a path");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn open_ledger(path: &std::path::Path) -> Ledger {
+    match Ledger::open(path) {
+        Ok(ledger) => ledger,
+        Err(e) => {
+            eprintln!("ledger {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn start_ledger_run(args: &GenArgs) -> Option<LedgerRun> {
+    let path = ledger_path(args.ledger.as_ref()?.as_ref());
+    let mut ledger = open_ledger(&path);
+    let issued = ledger.issued(&args.namespace).unwrap_or_else(|e| {
+        eprintln!("ledger {}: {}", path.display(), e);
+        std::process::exit(1);
+    });
+    Some(LedgerRun { ledger, issued })
+}
+
+/// Record the finished items in the ledger — before they are printed, so a
+/// value is never shown without being recorded.
+fn finish_batch(items: &[Styled], args: &GenArgs, run: &mut Option<LedgerRun>) {
+    if let Some(run) = run {
+        let values: Vec<String> = items.iter().map(|item| item.text.clone()).collect();
+        if let Err(e) = run.ledger.record(&args.namespace, &values) {
+            eprintln!("ledger: {}", e);
+            std::process::exit(1);
         }
+        run.issued.extend(values);
+    }
+}
+
+/// Items for one menu section: as many as fit `rows` rows at `width`, with
+/// room for labels under `-L`.
+fn section_items(
+    format: RandomFormat,
+    style: &Style,
+    args: &GenArgs,
+    width: usize,
+    rows: usize,
+    exclude: Option<&HashSet<String>>,
+) -> Vec<Styled> {
+    let length = args.length;
+    // "a12 " is as wide as a menu label gets.
+    let label_w = if args.labels { 4 } else { 0 };
+    // Generate a starter batch using the format's estimated width, then
+    // re-measure actual content and grow the batch to as many as could fit
+    // if every column were as narrow as the narrowest item.
+    let avail = width.saturating_sub(MENU_INDENT);
+    let est = estimated_width(format, length) + label_w;
+    let est_cols = std::cmp::max(1, avail / (est + COL_GAP));
+    let mut items = generate_batch(format, style, est_cols * rows, args, exclude);
+
+    let narrowest = items.iter().map(|s| display_width(&s.text) + label_w).min().unwrap_or(est);
+    let most = std::cmp::max(1, (avail + COL_GAP) / (narrowest + COL_GAP)) * rows;
+    if most > items.len() && exclude.is_some() {
+        // Growing piecemeal could repeat across the two batches.
+        items = generate_batch(format, style, most, args, exclude);
+    } else if most > items.len() {
+        items.extend(generate_batch(format, style, most - items.len(), args, None));
+    }
+
+    // Then keep the most that lay out as full rows with per-column widths,
+    // or failing that, the most that fit in the rows at all.
+    let widths: Vec<usize> = items.iter().map(|s| display_width(&s.text) + label_w).collect();
+    let fits = |k: usize| Grid::fit(&widths[..k], avail, COL_GAP, args.fill);
+    let keep = (1..=items.len())
+        .rev()
+        .find(|&k| {
+            let grid = fits(k);
+            grid.rows == rows && grid.rows * grid.cols() == k
+        })
+        .or_else(|| (1..=items.len()).rev().find(|&k| fits(k).rows <= rows))
+        .unwrap_or(1);
+    items.truncate(keep);
+    items
+}
 
+/// Heading and grid for one section of the menu, or of a several-format
+/// run. `theme` names the section when it isn't just the format.
+fn print_section(
+    section: usize,
+    theme: Option<&str>,
+    format: RandomFormat,
+    style: &Style,
+    items: &[Styled],
+    args: &GenArgs,
+    width: usize,
+) {
+    let color = args.color();
+    let (title, detail) = match theme {
+        Some(theme) => (theme, format!("{}, ", format.short_name())),
+        None => (format.short_name(), String::new()),
+    };
+    if color {
+        let length = args.length.or(format.default_length()).unwrap_or(0);
+        let bits = match format {
+            // Show what the weak-PIN filter costs, too.
+            RandomFormat::Pin => {
+                let e = randr::pin_entropy(length);
+                format!("{:.2} bits, {:.2} unfiltered", e.filtered, e.raw)
+            }
+            _ => format!("{:.0} bits", randr::output_space(format, style, length).log2()),
+        };
+        println!("\n{}{}{} {}({}{}){}", BOLD, title, RESET, DIM, detail, bits, RESET);
+    } else if let Some(theme) = theme {
         println!("\n{} ({})", theme, format.short_name());
-        print_grid(&items, indent, width);
+    } else {
+        println!("\n{}", title);
     }
+    print_grid(items, args.labels.then_some(section), MENU_INDENT, width, args.fill, color);
 }
 
-fn print_format(format: RandomFormat, count: Option<usize>) {
-    let style = Style::random();
+/// Print the menu, or the sections asked for; returns the items shown, per
+/// section. Themes get `-n` rows, formats `-n` items.
+fn print_sections(
+    sections: &[Section],
+    args: &GenArgs,
+    run: &mut Option<LedgerRun>,
+) -> Vec<Vec<String>> {
+    let mut rng = rand::thread_rng();
     let width = term_width();
-    let est = estimated_width(format);
-    let cols = std::cmp::max(1, width / (est + COL_GAP));
-    let n = count.unwrap_or(cols * 3);
-    let items = generate_batch(format, &style, n);
-    print_grid(&items, 0, width);
+    let shared = args.same_style.then(|| section_style(args));
+
+    let no_repeats = HashSet::new();
+    let mut shown = Vec::new();
+
+    for (index, section) in sections.iter().enumerate() {
+        let style = shared.clone().unwrap_or_else(|| section_style(args));
+        let exclude = match run {
+            Some(run) => Some(&run.issued),
+            None => args.unique.then_some(&no_repeats),
+        };
+        let (theme, format, items) = match *section {
+            Section::Theme(theme, pool) => {
+                let format = *pool.choose(&mut rng).unwrap();
+                let rows = args.count.unwrap_or(MAX_ROWS_PER_SECTION);
+                let items = section_items(format, &style, args, width, rows, exclude);
+                (Some(theme), format, items)
+            }
+            Section::Format(format) => {
+                let items = match args.count {
+                    Some(n) => generate_batch(format, &style, n, args, exclude),
+                    None => {
+                        section_items(format, &style, args, width, MAX_ROWS_PER_SECTION, exclude)
+                    }
+                };
+                (None, format, items)
+            }
+        };
+        finish_batch(&items, args, run);
+
+        print_section(index, theme, format, &style, &items, args, width);
+        shown.push(items.into_iter().map(|item| item.text).collect());
+    }
+    shown
 }
 
-fn main() {
-    let cli = Cli::parse();
+/// `-i`: pick one item from the menu (or from the sections asked for)
+/// interactively and print just that.
+fn pick_interactively(sections: &[Section], args: &GenArgs, run: &mut Option<LedgerRun>) {
+    let themes = sections
+        .iter()
+        .map(|section| match *section {
+            Section::Theme(theme, pool) => (Some(theme.to_string()), pool.to_vec()),
+            Section::Format(format) => (None, vec![format]),
+        })
+        .collect();
+    let shared = args.same_style.then(|| section_style(args));
+    let no_repeats = HashSet::new();
+    let exclude = match run {
+        Some(run) => Some(&run.issued),
+        None => args.unique.then_some(&no_repeats),
+    };
+    let picker = picker::Picker::new(
+        themes,
+        term_width(),
+        args.fill,
+        Box::new(move || shared.clone().unwrap_or_else(|| section_style(args))),
+        Box::new(|format, style, width| {
+            let rows = args.count.unwrap_or(MAX_ROWS_PER_SECTION);
+            let items = section_items(format, style, args, width, rows, exclude);
+            items.into_iter().map(|item| item.text).collect()
+        }),
+    );
+    let picked = match picker::run(picker) {
+        Ok(picked) => picked,
+        Err(e) => {
+            eprintln!("interactive mode: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let Some(item) = picked else {
+        std::process::exit(1);
+    };
+    let items = [Styled::from(item)];
+    finish_batch(&items, args, run);
+    println!("{}", items[0].text);
+    if args.copy.is_some() {
+        copy_to_clipboard(&items[0].text);
+    }
+}
 
-    if let Some(format) = &cli.format {
-        let wanted = format.to_lowercase();
-        let selected = RandomFormat::all()
-            .into_iter()
-            .find(|f| f.short_name() == wanted);
+/// Send `text` to the clipboard with OSC 52. The sequence goes to the
+/// controlling terminal rather than stdout, so it never ends up in a pipe.
+fn copy_to_clipboard(text: &str) {
+    let seq = randr::osc52(text, std::env::var_os("TMUX").is_some());
+    let written = match std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(mut tty) => tty.write_all(seq.as_bytes()),
+        Err(_) if std::io::stderr().is_terminal() => std::io::stderr().write_all(seq.as_bytes()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = written {
+        eprintln!("--copy: can't reach the terminal: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Find `wanted` among labelled items: by label (`b3`) or by number in
+/// printed order, counting from 1.
+fn find_item<'a>(items: &'a [(String, String)], wanted: &str) -> Option<&'a str> {
+    let found = match wanted.parse::<usize>() {
+        Ok(n) => n.checked_sub(1).and_then(|i| items.get(i)),
+        Err(_) => items.iter().find(|(label, _)| label.eq_ignore_ascii_case(wanted)),
+    };
+    found.map(|(_, value)| value.as_str())
+}
 
-        match selected {
-            Some(fmt) => print_format(fmt, cli.count),
+/// After a grid run: remember it for `randr last`, and handle `--copy`
+/// outside `-i`.
+fn finish_grid(sections: &[Vec<String>], args: &GenArgs) {
+    let items = last::labelled(sections);
+    if let Some(path) = last::default_path() {
+        // Only a convenience; don't fail the run over it.
+        let _ = last::save(&path, &items);
+    }
+    if let Some(wanted) = &args.copy {
+        let wanted = wanted.as_deref().unwrap_or("1");
+        match find_item(&items, wanted) {
+            Some(item) => copy_to_clipboard(item),
+            None => {
+                eprintln!("--copy={}: no such item among {} printed", wanted, items.len());
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn print_last(item: Option<&str>) {
+    let path = last::default_path();
+    let items = match path.as_deref().map(last::load) {
+        Some(Ok(items)) => items,
+        _ => {
+            eprintln!("no previous grid to pick from");
+            std::process::exit(1);
+        }
+    };
+    match item {
+        Some(wanted) => match find_item(&items, wanted) {
+            Some(value) => println!("{}", value),
             None => {
-                eprintln!("unknown format: {}", format);
+                eprintln!("no item {} in the last grid", wanted);
+                std::process::exit(1);
+            }
+        },
+        None => {
+            for (label, value) in &items {
+                println!("{}\t{}", label, value);
+            }
+        }
+    }
+}
+
+/// Items `randr <format>` prints without `-n`: a screenful at the
+/// estimated width, or FORMAT_ROWS rows when `height` is unknown.
+fn default_count(
+    format: RandomFormat,
+    length: Option<usize>,
+    width: usize,
+    height: Option<usize>,
+) -> usize {
+    let est = estimated_width(format, length);
+    let cols = std::cmp::max(1, width / (est + COL_GAP));
+    // Keep one line for the command and one for the next prompt.
+    let rows = height.map_or(FORMAT_ROWS, |h| h.saturating_sub(2).max(1));
+    cols * rows
+}
+
+fn print_format(format: RandomFormat, args: &GenArgs, run: &mut Option<LedgerRun>) -> Vec<String> {
+    let style = section_style(args);
+    let width = term_width();
+    let height = term_height();
+    let n = args
+        .count
+        .unwrap_or_else(|| default_count(format, args.length, width, height));
+    let no_repeats = HashSet::new();
+    let exclude = match run {
+        Some(run) => Some(&run.issued),
+        None => args.unique.then_some(&no_repeats),
+    };
+    let items = generate_batch(format, &style, n, args, exclude);
+    finish_batch(&items, args, run);
+
+    let lines = grid_lines(&items, args.labels.then_some(0), 0, width, args.fill, args.color());
+    let page = !args.no_pager && std::io::stdout().is_terminal();
+    match height {
+        Some(height) if page && lines.len() >= height => {
+            if let Err(e) = pager::page(&lines, height) {
+                eprintln!("pager: {}", e);
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            let mut out = io::stdout().lock();
+            check_write(lines.iter().try_for_each(|line| writeln!(out, "{}", line)));
+        }
+    }
+    items.into_iter().map(|item| item.text).collect()
+}
+
+/// `--stream`: values one per line, generated as they're written.
+fn print_stream(format: RandomFormat, args: &GenArgs) {
+    let mut master = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let style = section_style_with(args, &mut master);
+    let length = args.length.or(format.default_length()).unwrap_or(0);
+    let line = |value: &str, buf: &mut Vec<u8>| {
+        buf.extend_from_slice(value.as_bytes());
+        buf.push(b'\n');
+    };
+    let jobs = match args.jobs {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let job = bulk::Job {
+        format,
+        style,
+        length,
+        encoding: args.encoding.unwrap_or_default(),
+        count: args.count,
+        jobs,
+        line: &line,
+    };
+    let mut out = io::BufWriter::with_capacity(STREAM_BUFFER, io::stdout().lock());
+    check_write(bulk::run(&job, master, &mut out));
+}
+
+/// Exit on a failed write to stdout, quietly when the reader went away
+/// (`randr ... | head`): it has everything it wanted.
+fn check_write(result: io::Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_otp(
+    issuer: &str,
+    account: &str,
+    secret: Option<&str>,
+    length: usize,
+    digits: u32,
+    code: bool,
+) {
+    let secret = match secret {
+        Some(s) => s.to_string(),
+        None => randr::totp_secret(length),
+    };
+    let Some(key) = randr::base32_decode(&secret) else {
+        eprintln!("invalid base32 secret: {}", secret);
+        std::process::exit(1);
+    };
+    if !key.is_empty() && key.len() < randr::TOTP_SECRET_MIN_BYTES {
+        eprintln!(
+            "warning: the secret is {} bytes; RFC 4226 asks for at least {}",
+            key.len(),
+            randr::TOTP_SECRET_MIN_BYTES
+        );
+    }
+    let secret: String = secret.split_whitespace().collect::<String>().to_uppercase();
+    let secret = secret.trim_end_matches('=');
+
+    match randr::otpauth_uri(issuer, account, secret, digits) {
+        Ok(uri) => println!("{}", uri),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if code {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        match randr::totp_code(&key, now, digits) {
+            Ok(code) => println!("{}", code),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Probabilities span 1 down to ~1e-30; switch to exponent form once plain
+/// decimals would be all zeros.
+fn format_probability(p: f64) -> String {
+    if p == 0.0 {
+        "0".to_string()
+    } else if p >= 1e-3 {
+        format!("{:.4}", p)
+    } else {
+        format!("{:.2e}", p)
+    }
+}
+
+fn print_collide(
+    format: RandomFormat,
+    count: u64,
+    tag: Option<TagKind>,
+    length: Option<usize>,
+    max_p: f64,
+) {
+    if format == RandomFormat::Pin {
+        check_pin_length(length);
+    }
+    let length = length.or(format.default_length()).unwrap_or(0);
+    let candidates: Vec<randr::Candidate> = randr::candidates(format, length)
+        .into_iter()
+        .filter(|c| tag.is_none() || c.tag == tag)
+        .collect();
+
+    println!("{} × {}", format.short_name(), count);
+    println!("  {:<10} {:>6} {:>6}  P(collision)", "tag", "bits", "chars");
+    for c in &candidates {
+        let tag = c.tag.map(|t| t.short_name()).unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:<10} {:>6.1} {:>6.1}  {}",
+            tag,
+            c.bits,
+            c.mean_chars,
+            format_probability(c.probability(count))
+        );
+    }
+    if format == RandomFormat::Pin {
+        let e = randr::pin_entropy(length);
+        println!("  ({:.2} bits filtered, {:.2} before weak PINs are dropped)", e.filtered, e.raw);
+    }
+
+    match randr::cheapest(count, max_p) {
+        Some(best) => {
+            let mut what = best.format.short_name().to_string();
+            if let Some(tag) = best.tag {
+                what.push_str(&format!(" with a {} tag", tag.short_name()));
+            } else if best.format.default_length().is_some() {
+                what.push_str(&format!(" -l {}", best.length));
+            }
+            println!(
+                "\ncheapest with P ≤ {}: {} ({:.1} bits, ~{:.0} chars, P = {})",
+                max_p,
+                what,
+                best.bits,
+                best.mean_chars,
+                format_probability(best.probability(count))
+            );
+            println!("  (word formats vary only the tag: lowercase, `-` separators)");
+        }
+        None => println!("\nno format keeps P ≤ {} for {} values", max_p, count),
+    }
+}
+
+fn run_ledger_command(path: Option<&PathBuf>, action: &LedgerAction) {
+    let path = ledger_path(path);
+    let mut ledger = open_ledger(&path);
+    let result = match action {
+        LedgerAction::List { namespace: None } => ledger.namespaces().map(|counts| {
+            for (namespace, count) in counts {
+                println!("{}\t{}", namespace, count);
+            }
+        }),
+        LedgerAction::List {
+            namespace: Some(namespace),
+        } => ledger.entries().map(|entries| {
+            for entry in entries.iter().filter(|e| &e.namespace == namespace) {
+                println!("{}", entry.value);
+            }
+        }),
+        LedgerAction::Forget {
+            namespace,
+            values,
+            all,
+        } => {
+            let values = (!all).then_some(values.as_slice());
+            ledger
+                .forget(namespace, values)
+                .map(|n| eprintln!("forgot {} value(s) in {}", n, namespace))
+        }
+        LedgerAction::Export { namespace } => ledger.entries().map(|entries| {
+            for entry in entries {
+                if namespace.as_ref().is_none_or(|ns| &entry.namespace == ns) {
+                    println!("{}", entry.to_line());
+                }
+            }
+        }),
+    };
+    if let Err(e) = result {
+        eprintln!("ledger {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+}
+
+/// The sections asked for with positional names and `--theme`, or the whole
+/// menu when there are none. Positional names are format names first, then
+/// themes, then format prefixes (`const`).
+fn requested_sections(args: &GenArgs) -> Vec<Section> {
+    let mut sections: Vec<Section> = args
+        .formats
+        .iter()
+        .map(|name| {
+            if let Some(format) = RandomFormat::from_name(name) {
+                return Section::Format(format);
+            }
+            if let Some(theme) = Section::theme(&name.to_lowercase()) {
+                return theme;
+            }
+            match name.parse() {
+                Ok(format) => Section::Format(format),
+                Err(e) => unknown_name(&e),
+            }
+        })
+        .collect();
+    sections.extend(args.theme.iter().filter_map(|theme| Section::theme(theme)));
+    let named = !sections.is_empty();
+    if !named {
+        sections = THEMES.iter().map(|(theme, pool)| Section::Theme(theme, pool)).collect();
+    }
+    if let Some(constraint) = args.constraint() {
+        constrain_sections(&mut sections, constraint, named);
+    }
+    if sections.iter().any(|section| section.includes(RandomFormat::Pin)) {
+        check_pin_length(args.length);
+    }
+    sections
+}
+
+/// `-l` is shared by every sized format, so the PIN minimum can't live in
+/// its value parser; refuse it here with the same kind of error instead.
+fn check_pin_length(length: Option<usize>) {
+    if length.is_some_and(|length| length < randr::PIN_MIN_LENGTH) {
+        let msg = format!("a PIN needs at least {} digits", randr::PIN_MIN_LENGTH);
+        Cli::command().error(clap::error::ErrorKind::ValueValidation, msg).exit();
+    }
+}
+
+/// `--for` only shapes styled formats: leave the others out of the default
+/// menu, and refuse them when asked for by name rather than print values
+/// the target won't take.
+fn constrain_sections(sections: &mut Vec<Section>, constraint: Constraint, named: bool) {
+    if !named {
+        sections.retain(Section::is_styled);
+        return;
+    }
+    if let Some(section) = sections.iter().find(|section| !section.is_styled()) {
+        let msg = format!(
+            "--for {} only applies to word formats; `{}` has its own alphabet",
+            constraint.short_name(),
+            section.name()
+        );
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, msg).exit();
+    }
+}
+
+fn unknown_name(e: &ParseFormatError) -> ! {
+    eprintln!("{}", e);
+    if let ParseFormatError::Ambiguous { .. } = e {
+        std::process::exit(1);
+    }
+    eprintln!("formats:");
+    for fmt in RandomFormat::all() {
+        eprintln!("  {}", fmt.short_name());
+    }
+    eprintln!("themes:");
+    for (theme, _) in THEMES {
+        eprintln!("  {}", theme);
+    }
+    std::process::exit(1);
+}
+
+/// Parse a format name (see `RandomFormat::from_str`), or explain and exit.
+fn find_format(name: &str) -> RandomFormat {
+    match name.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("{}", e);
+            if let ParseFormatError::Unknown { .. } = e {
                 eprintln!("available:");
                 for fmt in RandomFormat::all() {
                     eprintln!("  {}", fmt.short_name());
                 }
-                std::process::exit(1);
             }
+            std::process::exit(1);
         }
+    }
+}
+
+fn print_completions(shell: Shell, out: &mut dyn Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "randr", out);
+}
+
+fn print_man(out: &mut dyn Write) -> io::Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(out)
+}
+
+fn generate(args: &GenArgs) {
+    let sections = requested_sections(args);
+    if args.stream {
+        let [Section::Format(format)] = sections[..] else {
+            eprintln!("--stream takes exactly one format");
+            std::process::exit(2);
+        };
+        return print_stream(format, args);
+    }
+    let mut run = start_ledger_run(args);
+    if args.interactive {
+        pick_interactively(&sections, args, &mut run);
+    } else if let [Section::Format(format)] = sections[..] {
+        let shown = print_format(format, args, &mut run);
+        finish_grid(&[shown], args);
     } else {
-        print_themed_menu();
+        let shown = print_sections(&sections, args, &mut run);
+        finish_grid(&shown, args);
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Some(Command::Otp {
+            issuer,
+            account,
+            secret,
+            length,
+            digits,
+            code,
+        }) => print_otp(issuer, account, secret.as_deref(), *length, *digits, *code),
+        Some(Command::Last { item }) => print_last(item.as_deref()),
+        Some(Command::Ledger { path, action }) => run_ledger_command(path.as_ref(), action),
+        Some(Command::Collide {
+            format,
+            count,
+            tag,
+            length,
+            max_p,
+        }) => print_collide(find_format(format), *count, *tag, *length, *max_p),
+        Some(Command::Completions { shell }) => print_completions(*shell, &mut io::stdout()),
+        Some(Command::Man) => {
+            if let Err(e) = print_man(&mut io::stdout()) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Gen(args)) => generate(args),
+        None => generate(&cli.gen),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["randr", "name", "-n", "3"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.gen.formats, ["name"]);
+        let cli = Cli::try_parse_from(["randr", "gen", "name", "-n", "3"]).unwrap();
+        let Some(Command::Gen(args)) = cli.command else {
+            panic!("expected gen");
+        };
+        assert_eq!((args.formats, args.count), (vec!["name".to_string()], Some(3)));
+        // Prefixes still reach `requested_sections` instead of being rejected.
+        assert!(Cli::try_parse_from(["randr", "cons"]).is_ok());
+    }
+
+    #[test]
+    fn test_constrained_menu() {
+        let cli = Cli::try_parse_from(["randr", "--for", "rust"]).unwrap();
+        let names: Vec<&str> = requested_sections(&cli.gen).iter().map(Section::name).collect();
+        assert_eq!(names, ["memorable", "place", "character"]);
+        let cli = Cli::try_parse_from(["randr"]).unwrap();
+        assert_eq!(requested_sections(&cli.gen).len(), THEMES.len());
+    }
+
+    #[test]
+    fn test_completions() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut out = Vec::new();
+            print_completions(shell, &mut out);
+            let script = String::from_utf8(out).unwrap();
+            insta::assert_snapshot!(format!("completions_{}", shell), script);
+        }
+    }
+
+    #[test]
+    fn test_man_page() {
+        let mut out = Vec::new();
+        print_man(&mut out).unwrap();
+        insta::assert_snapshot!(String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("café"), 4);
+        assert_eq!(display_width("e\u{301}te"), 3);
+        assert_eq!(display_width("東京"), 4);
+        assert_eq!(display_width("👩\u{200d}🔬"), 2);
+    }
+
+    #[test]
+    fn test_grid_unicode_alignment() {
+        let words = [
+            "café", "東京", "naïve", "👩\u{200d}🔬", "smörgåsbord", "ソウル", "Ελλάδα",
+        ];
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let style = Style::random();
+            let items: Vec<Styled> = (0..12)
+                .map(|_| {
+                    let pair: Vec<&str> = words.choose_multiple(&mut rng, 2).copied().collect();
+                    randr::render_words(&pair, &style).unwrap()
+                })
+                .collect();
+            for fill in [Fill::Rows, Fill::Columns] {
+                let lines = grid_lines(&items, Some(0), 2, 80, fill, false);
+
+                // Find each cell by its label (padded to "a12 ") and text;
+                // within a column, cells have to start at the same display
+                // column on every row.
+                let mut starts: Vec<Vec<usize>> = vec![Vec::new(); lines.len()];
+                for (i, item) in items.iter().enumerate() {
+                    let cell = format!("{:<4}{}", last::label(0, i), item.text);
+                    let (row, at) = lines
+                        .iter()
+                        .enumerate()
+                        .find_map(|(row, line)| line.find(&cell).map(|at| (row, at)))
+                        .unwrap();
+                    starts[row].push(lines[row][..at].width());
+                }
+                for row in starts.iter_mut() {
+                    row.sort();
+                }
+                for row in &starts[1..] {
+                    assert_eq!(row[..], starts[0][..row.len()], "{:#?}", lines);
+                }
+                assert!(lines.iter().all(|l| l.width() <= 80));
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_count() {
+        // Two 36-wide UUIDs per 80-column row.
+        assert_eq!(default_count(RandomFormat::Uuid, None, 80, None), 2 * FORMAT_ROWS);
+        // A screenful, less the command and the next prompt.
+        assert_eq!(default_count(RandomFormat::Uuid, None, 80, Some(50)), 2 * 48);
+        assert_eq!(default_count(RandomFormat::Uuid, None, 80, Some(4)), 2 * 2);
+        assert_eq!(default_count(RandomFormat::Uuid, None, 80, Some(1)), 2);
     }
 }
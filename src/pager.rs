@@ -0,0 +1,121 @@
+//! Paging for output taller than the terminal: `$PAGER` when set, else a
+//! small built-in `more`.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::io::{self, Write};
+use std::process::{Command, ExitStatus, Stdio};
+
+/// Show `lines` a screen (`height` rows) at a time.
+pub fn page(lines: &[String], height: usize) -> io::Result<()> {
+    match std::env::var("PAGER") {
+        Ok(pager) if !pager.trim().is_empty() => match external(&pager, lines)? {
+            // `sh` couldn't start, or couldn't find or run the pager (126,
+            // 127): page it ourselves rather than lose the output.
+            None => builtin(lines, height),
+            Some(status) if matches!(status.code(), Some(126 | 127)) => builtin(lines, height),
+            Some(_) => Ok(()),
+        },
+        _ => builtin(lines, height),
+    }
+}
+
+/// Pipe `lines` through `sh -c pager`; `None` if `sh` wouldn't start.
+fn external(pager: &str, lines: &[String]) -> io::Result<Option<ExitStatus>> {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(pager).stdin(Stdio::piped());
+    // Like git: let less pass colours through and exit if it all fits.
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let Ok(mut child) = command.spawn() else {
+        return Ok(None);
+    };
+    let mut stdin = child.stdin.take().unwrap();
+    for line in lines {
+        if let Err(e) = writeln!(stdin, "{}", line) {
+            // The user quit the pager before reading everything.
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                break;
+            }
+            return Err(e);
+        }
+    }
+    drop(stdin);
+    child.wait().map(Some)
+}
+
+/// What a key does at the `--More--` prompt.
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    Page,
+    Line,
+    Quit,
+}
+
+fn step(code: KeyCode, modifiers: KeyModifiers) -> Option<Step> {
+    match code {
+        KeyCode::Char(' ') | KeyCode::PageDown | KeyCode::Char('f') => Some(Step::Page),
+        KeyCode::Enter | KeyCode::Down | KeyCode::Char('j') => Some(Step::Line),
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Some(Step::Quit),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Step::Quit),
+        _ => None,
+    }
+}
+
+fn builtin(lines: &[String], height: usize) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    let screen = height.saturating_sub(1).max(1);
+    let mut shown = screen.min(lines.len());
+    for line in &lines[..shown] {
+        writeln!(out, "{}", line)?;
+    }
+    while shown < lines.len() {
+        write!(out, "\x1b[7m--More-- ({}/{})\x1b[0m", shown, lines.len())?;
+        out.flush()?;
+        let next = read_step()?;
+        write!(out, "\r\x1b[K")?;
+        let more = match next {
+            Step::Page => screen,
+            Step::Line => 1,
+            Step::Quit => break,
+        };
+        let end = (shown + more).min(lines.len());
+        for line in &lines[shown..end] {
+            writeln!(out, "{}", line)?;
+        }
+        shown = end;
+    }
+    out.flush()
+}
+
+fn read_step() -> io::Result<Step> {
+    enable_raw_mode()?;
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if let Some(step) = step(key.code, key.modifiers) {
+                    break Ok(step);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        assert_eq!(step(KeyCode::Char(' '), KeyModifiers::NONE), Some(Step::Page));
+        assert_eq!(step(KeyCode::Enter, KeyModifiers::NONE), Some(Step::Line));
+        assert_eq!(step(KeyCode::Char('q'), KeyModifiers::NONE), Some(Step::Quit));
+        assert_eq!(step(KeyCode::Char('c'), KeyModifiers::CONTROL), Some(Step::Quit));
+        assert_eq!(step(KeyCode::Char('x'), KeyModifiers::NONE), None);
+    }
+}
@@ -0,0 +1,387 @@
+//! Interactive picker for `randr -i`: the menu (or one format) as a grid you
+//! move around with the arrow keys. Draws on stderr so the chosen value is
+//! the only thing written to stdout — `name=$(randr -i)` works.
+
+use crate::layout::{self, Grid};
+use crate::{display_width, COL_GAP, MENU_INDENT as INDENT};
+use rand::seq::SliceRandom;
+use randr::{RandomFormat, Style};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::Modifier;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use ratatui::{Frame, Terminal};
+use std::io;
+
+const HELP: &str = "←↓↑→ move  r reroll  R reroll all  p pin style  ⏎ pick  q quit";
+
+struct Section {
+    /// `None` for a format asked for by name, headed by the format alone.
+    theme: Option<String>,
+    pool: Vec<RandomFormat>,
+    format: RandomFormat,
+    style: Style,
+    items: Vec<String>,
+    /// Keep format and style on reroll; only the items change.
+    pinned: bool,
+}
+
+pub enum Outcome {
+    Picked(String),
+    Quit,
+}
+
+type NewStyle<'a> = Box<dyn Fn() -> Style + 'a>;
+/// Fills a section: (format, style, terminal width) → items.
+type Fill<'a> = Box<dyn Fn(RandomFormat, &Style, usize) -> Vec<String> + 'a>;
+
+pub struct Picker<'a> {
+    sections: Vec<Section>,
+    /// Cursor: section index and item index within it.
+    section: usize,
+    item: usize,
+    width: usize,
+    /// `--fill`: the order cells go in, as in the printed grid.
+    order: layout::Fill,
+    new_style: NewStyle<'a>,
+    fill: Fill<'a>,
+}
+
+impl<'a> Picker<'a> {
+    /// One section per `(theme, format pool)`, each rolled like a menu
+    /// section: a format from the pool, a style from `new_style`, items
+    /// from `fill`. Headings match the printed menu's.
+    pub fn new(
+        themes: Vec<(Option<String>, Vec<RandomFormat>)>,
+        width: usize,
+        order: layout::Fill,
+        new_style: NewStyle<'a>,
+        fill: Fill<'a>,
+    ) -> Self {
+        let mut rng = rand::thread_rng();
+        let sections = themes
+            .into_iter()
+            .map(|(theme, pool)| {
+                let format = *pool.choose(&mut rng).unwrap();
+                let style = new_style();
+                let items = fill(format, &style, width);
+                Section {
+                    theme,
+                    pool,
+                    format,
+                    style,
+                    items,
+                    pinned: false,
+                }
+            })
+            .collect();
+        Picker {
+            sections,
+            section: 0,
+            item: 0,
+            width,
+            order,
+            new_style,
+            fill,
+        }
+    }
+
+    pub fn resize(&mut self, width: usize) {
+        self.width = width;
+    }
+
+    /// Where a section's items go: the same per-column layout as the
+    /// printed menu.
+    fn grid(&self, section: usize) -> Grid {
+        let widths: Vec<usize> =
+            self.sections[section].items.iter().map(|s| display_width(s)).collect();
+        Grid::fit(&widths, self.width.saturating_sub(INDENT), COL_GAP, self.order)
+    }
+
+    fn reroll(&mut self, index: usize) {
+        let section = &mut self.sections[index];
+        if !section.pinned {
+            section.format = *section.pool.choose(&mut rand::thread_rng()).unwrap();
+            section.style = (self.new_style)();
+        }
+        section.items = (self.fill)(section.format, &section.style, self.width);
+        if index == self.section {
+            self.item = self.item.min(section.items.len().saturating_sub(1));
+        }
+    }
+
+    fn selected(&self) -> Option<&String> {
+        self.sections.get(self.section)?.items.get(self.item)
+    }
+
+    /// Apply one key press. Returns `Some` once the user picks or quits.
+    pub fn handle(&mut self, key: KeyEvent) -> Option<Outcome> {
+        let rows = self.grid(self.section).rows();
+        let (row, col) = rows
+            .iter()
+            .enumerate()
+            .find_map(|(r, cells)| Some((r, cells.iter().position(|&i| i == self.item)?)))
+            .unwrap_or((0, 0));
+        // Left and right step through items in reading order, row by row.
+        let order: Vec<usize> = rows.concat();
+        let at = order.iter().position(|&i| i == self.item).unwrap_or(0);
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') if at > 0 => self.item = order[at - 1],
+            KeyCode::Right | KeyCode::Char('l') if at + 1 < order.len() => {
+                self.item = order[at + 1]
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(next) = rows.get(row + 1) {
+                    self.item = next[col.min(next.len() - 1)];
+                } else if self.section + 1 < self.sections.len() {
+                    self.section += 1;
+                    self.item = self.cell(self.section, 0, col);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if row > 0 {
+                    self.item = rows[row - 1][col.min(rows[row - 1].len() - 1)];
+                } else if self.section > 0 {
+                    self.section -= 1;
+                    let last = self.grid(self.section).rows - 1;
+                    self.item = self.cell(self.section, last, col);
+                }
+            }
+            KeyCode::Char('r') => self.reroll(self.section),
+            KeyCode::Char('R') => (0..self.sections.len()).for_each(|i| self.reroll(i)),
+            KeyCode::Char('p') => {
+                let section = &mut self.sections[self.section];
+                section.pinned = !section.pinned;
+            }
+            KeyCode::Enter => return self.selected().cloned().map(Outcome::Picked),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(Outcome::Quit)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Quit),
+            _ => {}
+        }
+        None
+    }
+
+    /// The item at `row`, `col` of `section`, or the row's last one when
+    /// it's shorter.
+    fn cell(&self, section: usize, row: usize, col: usize) -> usize {
+        let rows = self.grid(section).rows();
+        let row = rows.get(row).map_or(&[][..], |r| &r[..]);
+        row.get(col.min(row.len().saturating_sub(1))).copied().unwrap_or(0)
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [body, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        let mut lines = Vec::new();
+        let mut cursor_line = 0;
+        for (s, section) in self.sections.iter().enumerate() {
+            if s > 0 {
+                lines.push(Line::default());
+            }
+            let format = section.format.short_name();
+            let mut heading = match &section.theme {
+                Some(theme) => format!("{} ({})", theme, format),
+                None => format.to_string(),
+            };
+            if section.pinned {
+                heading.push_str("  [pinned]");
+            }
+            lines.push(Line::from(heading));
+
+            let grid = self.grid(s);
+            for row in grid.rows() {
+                let mut spans = vec![Span::raw(" ".repeat(INDENT))];
+                for (c, &i) in row.iter().enumerate() {
+                    if c > 0 {
+                        spans.push(Span::raw(" ".repeat(COL_GAP)));
+                    }
+                    let item = &section.items[i];
+                    let pad = grid.widths[c] - display_width(item);
+                    let cell = format!("{}{}", item, " ".repeat(pad));
+                    if s == self.section && i == self.item {
+                        cursor_line = lines.len();
+                        spans.push(Span::styled(cell, Modifier::REVERSED));
+                    } else {
+                        spans.push(Span::raw(cell));
+                    }
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+
+        // Keep the cursor on screen when the menu is taller than the terminal.
+        let scroll = cursor_line.saturating_sub(body.height.saturating_sub(1) as usize);
+        frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), body);
+        frame.render_widget(Paragraph::new(Span::styled(HELP, Modifier::DIM)), footer);
+    }
+}
+
+/// Drive `picker` on the real terminal until the user picks or quits.
+pub fn run(mut picker: Picker) -> io::Result<Option<String>> {
+    // A panic mid-pick would otherwise leave the shell in raw mode on the
+    // alternate screen, with the message drawn where nobody sees it.
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+    let result = Terminal::new(CrosstermBackend::new(io::stderr()))
+        .and_then(|mut terminal| event_loop(&mut terminal, &mut picker));
+    // Back to the default hook; randr installs no other.
+    drop(std::panic::take_hook());
+    restore_terminal()?;
+    result
+}
+
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stderr(), LeaveAlternateScreen)
+}
+
+fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    picker: &mut Picker,
+) -> io::Result<Option<String>> {
+    loop {
+        terminal.draw(|frame| picker.draw(frame))?;
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => match picker.handle(key) {
+                Some(Outcome::Picked(item)) => return Ok(Some(item)),
+                Some(Outcome::Quit) => return Ok(None),
+                None => {}
+            },
+            Event::Resize(width, _) => picker.resize(width as usize),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use std::cell::Cell;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    /// Two sections of six fixed-width items, numbered by fill call so
+    /// rerolls are visible: `a0-0` .. `a0-5`, then `b1-0` ...
+    fn picker(calls: &Cell<usize>) -> Picker<'_> {
+        let themes = vec![
+            (Some("first".to_string()), vec![RandomFormat::MemorableName]),
+            (Some("second".to_string()), vec![RandomFormat::GeographicName]),
+        ];
+        let fill = move |format: RandomFormat, _: &Style, _: usize| {
+            let n = calls.get();
+            calls.set(n + 1);
+            let tag = if format == RandomFormat::MemorableName { 'a' } else { 'b' };
+            (0..6).map(|i| format!("{}{}-{}", tag, n, i)).collect()
+        };
+        // Width 20: indent 2, items 4 wide + gap 2 → 3 columns, 2 rows.
+        Picker::new(themes, 20, layout::Fill::Rows, Box::new(Style::random), Box::new(fill))
+    }
+
+    fn pick(p: &mut Picker) -> String {
+        match p.handle(key(KeyCode::Enter)) {
+            Some(Outcome::Picked(item)) => item,
+            _ => panic!("enter should pick"),
+        }
+    }
+
+    #[test]
+    fn test_navigation() {
+        let calls = Cell::new(0);
+        let mut p = picker(&calls);
+        assert_eq!(pick(&mut p), "a0-0");
+
+        p.handle(key(KeyCode::Right));
+        p.handle(key(KeyCode::Down));
+        assert_eq!(pick(&mut p), "a0-4");
+
+        // Down off the last row moves into the next section, same column.
+        p.handle(key(KeyCode::Down));
+        assert_eq!(pick(&mut p), "b1-1");
+
+        // And back up lands on the previous section's last row.
+        p.handle(key(KeyCode::Up));
+        assert_eq!(pick(&mut p), "a0-4");
+
+        p.handle(key(KeyCode::Left));
+        p.handle(key(KeyCode::Left));
+        p.handle(key(KeyCode::Left));
+        assert_eq!(pick(&mut p), "a0-1");
+        assert!(matches!(p.handle(key(KeyCode::Char('q'))), Some(Outcome::Quit)));
+    }
+
+    #[test]
+    fn test_reroll_and_pin() {
+        let calls = Cell::new(0);
+        let mut p = picker(&calls);
+        let style = format!("{:?}", p.sections[0].style);
+
+        p.handle(key(KeyCode::Char('p')));
+        p.handle(key(KeyCode::Char('r')));
+        assert_eq!(pick(&mut p), "a2-0");
+        assert_eq!(format!("{:?}", p.sections[0].style), style);
+        assert_eq!(p.sections[1].items[0], "b1-0");
+
+        p.handle(key(KeyCode::Char('R')));
+        assert_eq!(p.sections[0].items[0], "a3-0");
+        assert_eq!(p.sections[1].items[0], "b4-0");
+    }
+
+    #[test]
+    fn test_per_column_widths() {
+        // One long item doesn't push everything into a single column.
+        let fill = |_: RandomFormat, _: &Style, _: usize| {
+            ["abcdefgh", "b", "c", "d", "e", "f"].map(String::from).to_vec()
+        };
+        let themes = vec![(None, vec![RandomFormat::MemorableName])];
+        let mut p =
+            Picker::new(themes, 20, layout::Fill::Rows, Box::new(Style::random), Box::new(fill));
+        p.handle(key(KeyCode::Right));
+        p.handle(key(KeyCode::Down));
+        assert_eq!(pick(&mut p), "f");
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 4)).unwrap();
+        terminal.draw(|frame| p.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String { (0..20).map(|x| buffer[(x, y)].symbol()).collect() };
+        assert_eq!(row(1).trim_end(), "  abcdefgh  b  c  d");
+        assert_eq!(row(2).trim_end(), "  e         f");
+    }
+
+    #[test]
+    fn test_draw() {
+        let calls = Cell::new(0);
+        let mut p = picker(&calls);
+        p.handle(key(KeyCode::Right));
+        p.handle(key(KeyCode::Char('p')));
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 12)).unwrap();
+        terminal.draw(|frame| p.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String { (0..20).map(|x| buffer[(x, y)].symbol()).collect() };
+
+        assert_eq!(row(0).trim_end(), "first (name)  [pinne");
+        assert_eq!(row(1).trim_end(), "  a0-0  a0-1  a0-2");
+        assert_eq!(row(4).trim_end(), "second (geo)");
+        // Only the cursor cell is highlighted.
+        assert!(buffer[(8, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(!buffer[(2, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(row(11).starts_with("←↓↑→ move"));
+    }
+}